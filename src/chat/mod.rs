@@ -0,0 +1,600 @@
+use crate::irc::{self, ChatMessage, parse_badges, parse_color};
+use eframe::egui::Context;
+use futures_util::{SinkExt, StreamExt};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{UnboundedSender, unbounded_channel};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::{self, Message as Frame};
+
+/// Twitch chat, IRC over a websocket.
+pub const TWITCH_IRC_URL: &str = "wss://irc-ws.chat.twitch.tv:443";
+
+/// Lines of chat kept for the panel.
+const CHAT_LINES: usize = 500;
+
+/// Twitch allows 20 messages every 30 seconds, or 100 for moderators and VIPs, and locks out
+/// users that send more.
+const RATE_WINDOW: Duration = Duration::from_secs(30);
+const MESSAGES_PER_WINDOW: f64 = 20.0;
+const PRIVILEGED_MESSAGES_PER_WINDOW: f64 = 100.0;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChatLine {
+    Message(ChatMessage),
+    /// Something twitch or streamgui itself has to say, like a failed login.
+    Notice(String),
+    /// A notice about a message of ours twitch refused, like being banned or the room being
+    /// followers-only.
+    Error(String),
+}
+
+/// Why a message wasn't sent.
+#[derive(Debug, PartialEq)]
+pub enum SendError {
+    NotJoined,
+    /// Sent too many messages lately, can send again after this long.
+    RateLimited(Duration),
+    /// The room is in slow mode, can send again after this long.
+    SlowMode(Duration),
+}
+
+impl fmt::Display for SendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SendError::NotJoined => write!(f, "not connected to chat"),
+            SendError::RateLimited(wait) => write!(
+                f,
+                "sending too fast, wait {} seconds",
+                wait.as_secs_f64().ceil()
+            ),
+            SendError::SlowMode(wait) => write!(
+                f,
+                "slow mode is on, wait {} seconds",
+                wait.as_secs_f64().ceil()
+            ),
+        }
+    }
+}
+
+/// Keeps to twitch's limits on sending before twitch has to enforce them. A token bucket holds
+/// half of the messages allowed in a window and refills the other half over the window, so no
+/// window ever sees more than the limit.
+#[derive(Debug)]
+pub struct SendLimiter {
+    tokens: f64,
+    updated: Option<Instant>,
+    last_sent: Option<Instant>,
+    /// Seconds between messages from the room's slow mode, 0 when off.
+    pub slow: u64,
+    /// Moderators, VIPs and the broadcaster get a higher limit and ignore slow mode.
+    pub privileged: bool,
+}
+
+impl Default for SendLimiter {
+    fn default() -> Self {
+        Self {
+            tokens: MESSAGES_PER_WINDOW / 2.0,
+            updated: None,
+            last_sent: None,
+            slow: 0,
+            privileged: false,
+        }
+    }
+}
+
+impl SendLimiter {
+    fn burst(&self) -> f64 {
+        let limit = if self.privileged {
+            PRIVILEGED_MESSAGES_PER_WINDOW
+        } else {
+            MESSAGES_PER_WINDOW
+        };
+        limit / 2.0
+    }
+
+    /// Take a message's worth of the limits at `now`, or say how long until there is one.
+    pub fn check(&mut self, now: Instant) -> Result<(), SendError> {
+        let burst = self.burst();
+        let per_second = burst / RATE_WINDOW.as_secs_f64();
+
+        if let Some(updated) = self.updated {
+            let elapsed = now.saturating_duration_since(updated).as_secs_f64();
+            self.tokens = (self.tokens + elapsed * per_second).min(burst);
+        }
+        self.updated = Some(now);
+
+        if !self.privileged
+            && self.slow > 0
+            && let Some(last_sent) = self.last_sent
+        {
+            let next = last_sent + Duration::from_secs(self.slow);
+            if now < next {
+                return Err(SendError::SlowMode(next - now));
+            }
+        }
+        if self.tokens < 1.0 {
+            let wait = (1.0 - self.tokens) / per_second;
+            return Err(SendError::RateLimited(Duration::from_secs_f64(wait)));
+        }
+
+        self.tokens -= 1.0;
+        self.last_sent = Some(now);
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChatState {
+    Connecting,
+    Joined,
+    Disconnected,
+}
+
+struct Shared {
+    lines: VecDeque<ChatLine>,
+    state: ChatState,
+    limiter: SendLimiter,
+    /// How our own messages look, from the USERSTATE twitch sends on joining.
+    own: Option<ChatMessage>,
+}
+
+/// A connection to the chat of one channel, closed when dropped.
+pub struct Chat {
+    /// Login of the channel.
+    pub channel: String,
+    shared: Arc<Mutex<Shared>>,
+    outgoing: UnboundedSender<String>,
+    task: JoinHandle<()>,
+}
+
+impl Chat {
+    /// Connect to `url` as `login` with the user's access token and join `channel`. `ctx` is
+    /// repainted as messages come in.
+    pub fn connect(url: &str, login: &str, token: &str, channel: &str, ctx: Context) -> Self {
+        let shared = Arc::new(Mutex::new(Shared {
+            lines: VecDeque::new(),
+            state: ChatState::Connecting,
+            limiter: SendLimiter::default(),
+            own: None,
+        }));
+        let channel = channel.to_lowercase();
+        let (outgoing, outgoing_recv) = unbounded_channel();
+
+        let task = tokio::spawn({
+            let shared = shared.clone();
+            let url = url.to_owned();
+            let login = login.to_lowercase();
+            let token = token.to_owned();
+            let channel = channel.clone();
+
+            async move {
+                let connection = Connection {
+                    url: &url,
+                    login: &login,
+                    token: &token,
+                    channel: &channel,
+                };
+                let result = connection.run(&shared, outgoing_recv, &ctx).await;
+
+                let mut shared = shared.lock().unwrap();
+                shared.state = ChatState::Disconnected;
+                let reason = match result {
+                    Ok(()) => "Disconnected from chat".to_owned(),
+                    Err(e) => format!("Disconnected from chat: {}", e),
+                };
+                push(&mut shared.lines, ChatLine::Notice(reason));
+                ctx.request_repaint();
+            }
+        });
+
+        Self {
+            channel,
+            shared,
+            outgoing,
+            task,
+        }
+    }
+
+    /// Send `text` to the channel, if the rate limits and slow mode allow it. Twitch doesn't echo
+    /// our own messages, so it is added to the lines here.
+    pub fn send(&self, text: &str) -> Result<(), SendError> {
+        // a line break would end the PRIVMSG early and send the rest as a command
+        let text = text.replace(['\r', '\n'], " ");
+        let mut shared = self.shared.lock().unwrap();
+
+        if shared.state != ChatState::Joined {
+            return Err(SendError::NotJoined);
+        }
+        shared.limiter.check(Instant::now())?;
+
+        self.outgoing
+            .send(format!("PRIVMSG #{} :{}", self.channel, text))
+            .map_err(|_| SendError::NotJoined)?;
+
+        if let Some(own) = &shared.own {
+            let message = ChatMessage {
+                text,
+                ..own.clone()
+            };
+            push(&mut shared.lines, ChatLine::Message(message));
+        }
+        Ok(())
+    }
+
+    /// Run `f` over the lines while they are locked, so they can be drawn without copying them.
+    pub fn with_lines<R>(&self, f: impl FnOnce(&VecDeque<ChatLine>) -> R) -> R {
+        f(&self.shared.lock().unwrap().lines)
+    }
+
+    pub fn state(&self) -> ChatState {
+        self.shared.lock().unwrap().state
+    }
+}
+
+impl Drop for Chat {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn push(lines: &mut VecDeque<ChatLine>, line: ChatLine) {
+    if lines.len() == CHAT_LINES {
+        lines.pop_front();
+    }
+    lines.push_back(line);
+}
+
+struct Connection<'a> {
+    url: &'a str,
+    login: &'a str,
+    token: &'a str,
+    channel: &'a str,
+}
+
+impl Connection<'_> {
+    async fn run(
+        &self,
+        shared: &Mutex<Shared>,
+        mut outgoing: tokio::sync::mpsc::UnboundedReceiver<String>,
+        ctx: &Context,
+    ) -> Result<(), tungstenite::Error> {
+        let (socket, _) = connect_async(self.url).await?;
+        let (mut write, mut read) = socket.split();
+
+        for line in [
+            "CAP REQ :twitch.tv/tags twitch.tv/commands".to_owned(),
+            format!("PASS oauth:{}", self.token),
+            format!("NICK {}", self.login),
+            format!("JOIN #{}", self.channel),
+        ] {
+            write.send(Frame::text(line)).await?;
+        }
+
+        loop {
+            let frame = tokio::select! {
+                frame = read.next() => frame,
+                Some(line) = outgoing.recv() => {
+                    write.send(Frame::text(line)).await?;
+                    continue;
+                }
+            };
+            let text = match frame.transpose()? {
+                Some(Frame::Text(text)) => text,
+                Some(Frame::Close(_)) | None => break,
+                Some(_) => continue,
+            };
+
+            // a frame can hold several lines
+            for line in text.as_str().lines() {
+                let Some(message) = irc::Message::parse(line) else {
+                    continue;
+                };
+
+                match message.command.as_str() {
+                    "PING" => {
+                        let pong = format!("PONG :{}", message.trailing().unwrap_or_default());
+                        write.send(Frame::text(pong)).await?;
+                    }
+                    // twitch is about to restart the server
+                    "RECONNECT" => return Ok(()),
+                    _ => self.handle(&message, &mut shared.lock().unwrap()),
+                }
+            }
+            ctx.request_repaint();
+        }
+
+        Ok(())
+    }
+
+    fn handle(&self, message: &irc::Message, shared: &mut Shared) {
+        match message.command.as_str() {
+            "PRIVMSG" => {
+                if let Some(chat_message) = ChatMessage::from_message(message) {
+                    push(&mut shared.lines, ChatLine::Message(chat_message));
+                }
+            }
+            "NOTICE" => {
+                let notice = message.trailing().unwrap_or_default().to_owned();
+                // notices about messages twitch refused have ids like msg_banned
+                let refused = message
+                    .tag("msg-id")
+                    .is_some_and(|id| id.starts_with("msg_"));
+
+                if refused {
+                    push(&mut shared.lines, ChatLine::Error(notice));
+                } else {
+                    push(&mut shared.lines, ChatLine::Notice(notice));
+                }
+            }
+            // sent once the channel is joined, and again with only what changed
+            "ROOMSTATE" => {
+                shared.state = ChatState::Joined;
+                if let Some(slow) = message.tag("slow").and_then(|slow| slow.parse().ok()) {
+                    shared.limiter.slow = slow;
+                }
+            }
+            // sent on joining and after each message we send
+            "USERSTATE" => {
+                let badges = message.tag("badges").map(parse_badges).unwrap_or_default();
+
+                shared.limiter.privileged = message.tag("mod") == Some("1")
+                    || badges.iter().any(|badge| {
+                        matches!(badge.name.as_str(), "broadcaster" | "moderator" | "vip")
+                    });
+                shared.own = Some(ChatMessage {
+                    login: self.login.to_owned(),
+                    display_name: message.tag("display-name").unwrap_or(self.login).to_owned(),
+                    color: message.tag("color").and_then(parse_color),
+                    badges,
+                    emotes: Vec::new(),
+                    text: String::new(),
+                    action: false,
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+    use tokio::net::TcpListener;
+    use tokio::runtime::Runtime;
+    use tokio_tungstenite::accept_async;
+
+    fn lines(chat: &Chat) -> Vec<ChatLine> {
+        chat.with_lines(|lines| lines.iter().cloned().collect())
+    }
+
+    /// Wait for `done` to hold, giving up after a few seconds.
+    fn wait_for(chat: &Chat, done: impl Fn(&Chat) -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+
+        while !done(chat) {
+            assert!(
+                Instant::now() < deadline,
+                "timed out, chat has {:?}",
+                lines(chat)
+            );
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn joins_and_reads_messages_from_a_local_server() {
+        let rt = Runtime::new().unwrap();
+        let _enter = rt.enter();
+        let listener = rt.block_on(TcpListener::bind("127.0.0.1:0")).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        // stands in for twitch, checking the login then sending a little chat
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = accept_async(stream).await.unwrap();
+
+            let mut received = Vec::new();
+            while received.len() < 4 {
+                if let Some(Ok(Frame::Text(text))) = socket.next().await {
+                    received.push(text.as_str().to_owned());
+                }
+            }
+            assert_eq!(
+                received,
+                [
+                    "CAP REQ :twitch.tv/tags twitch.tv/commands",
+                    "PASS oauth:secret",
+                    "NICK viewer",
+                    "JOIN #somestreamer",
+                ]
+            );
+
+            let lines = "@room-id=1337;slow=0 :tmi.twitch.tv ROOMSTATE #somestreamer\r\n\
+                @color=#FF0000;display-name=Chatter :chatter!chatter@chatter.tmi.twitch.tv \
+                PRIVMSG #somestreamer :hello there\r\n";
+            socket.send(Frame::text(lines)).await.unwrap();
+            socket
+                .send(Frame::text("PING :tmi.twitch.tv\r\n"))
+                .await
+                .unwrap();
+
+            loop {
+                if let Some(Ok(Frame::Text(text))) = socket.next().await {
+                    assert_eq!(text.as_str(), "PONG :tmi.twitch.tv");
+                    break;
+                }
+            }
+            socket
+                .send(Frame::text(
+                    ":tmi.twitch.tv NOTICE #somestreamer :This room is in followers-only mode.",
+                ))
+                .await
+                .unwrap();
+            socket.close(None).await.unwrap();
+        });
+
+        let chat = Chat::connect(
+            &format!("ws://{}", addr),
+            "Viewer",
+            "secret",
+            "SomeStreamer",
+            Context::default(),
+        );
+        assert_eq!(chat.channel, "somestreamer");
+
+        wait_for(&chat, |chat| chat.state() == ChatState::Disconnected);
+        rt.block_on(server).unwrap();
+
+        let lines = lines(&chat);
+        let ChatLine::Message(message) = &lines[0] else {
+            panic!("expected a message, got {:?}", lines);
+        };
+        assert_eq!(message.display_name, "Chatter");
+        assert_eq!(message.color, Some([255, 0, 0]));
+        assert_eq!(message.text, "hello there");
+        assert_eq!(
+            lines[1],
+            ChatLine::Notice("This room is in followers-only mode.".to_owned())
+        );
+        assert_eq!(
+            lines[2],
+            ChatLine::Notice("Disconnected from chat".to_owned())
+        );
+    }
+
+    #[test]
+    fn sends_messages_and_shows_refusals() {
+        let rt = Runtime::new().unwrap();
+        let _enter = rt.enter();
+        let listener = rt.block_on(TcpListener::bind("127.0.0.1:0")).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = accept_async(stream).await.unwrap();
+
+            let lines = "@emote-only=0;followers-only=-1;slow=0 :tmi.twitch.tv ROOMSTATE #somestreamer\r\n\
+                @badges=;color=#00FF7F;display-name=Viewer;mod=0 :tmi.twitch.tv USERSTATE #somestreamer\r\n";
+            socket.send(Frame::text(lines)).await.unwrap();
+
+            loop {
+                if let Some(Ok(Frame::Text(text))) = socket.next().await
+                    && text.as_str().starts_with("PRIVMSG")
+                {
+                    assert_eq!(text.as_str(), "PRIVMSG #somestreamer :hi  there");
+                    break;
+                }
+            }
+            socket
+                .send(Frame::text(
+                    "@msg-id=msg_banned :tmi.twitch.tv NOTICE #somestreamer \
+                    :You are permanently banned from talking in somestreamer.",
+                ))
+                .await
+                .unwrap();
+            socket.close(None).await.unwrap();
+        });
+
+        let chat = Chat::connect(
+            &format!("ws://{}", addr),
+            "viewer",
+            "secret",
+            "somestreamer",
+            Context::default(),
+        );
+        assert_eq!(chat.send("too early"), Err(SendError::NotJoined));
+
+        wait_for(&chat, |chat| chat.shared.lock().unwrap().own.is_some());
+        chat.send("hi\r\nthere").unwrap();
+
+        wait_for(&chat, |chat| chat.state() == ChatState::Disconnected);
+        rt.block_on(server).unwrap();
+
+        let lines = lines(&chat);
+        let ChatLine::Message(message) = &lines[0] else {
+            panic!("expected our message, got {:?}", lines);
+        };
+        assert_eq!(message.display_name, "Viewer");
+        assert_eq!(message.color, Some([0, 255, 127]));
+        assert_eq!(message.text, "hi  there");
+        assert_eq!(
+            lines[1],
+            ChatLine::Error("You are permanently banned from talking in somestreamer.".to_owned())
+        );
+    }
+
+    #[test]
+    fn limiter_refills_over_the_window() {
+        let mut limiter = SendLimiter::default();
+        let start = Instant::now();
+
+        for _ in 0..10 {
+            limiter.check(start).unwrap();
+        }
+        match limiter.check(start) {
+            Err(SendError::RateLimited(wait)) => {
+                assert_eq!(wait.as_secs_f64().round(), 3.0)
+            }
+            result => panic!("expected to be rate limited, got {:?}", result),
+        }
+
+        limiter.check(start + Duration::from_secs(3)).unwrap();
+        assert!(limiter.check(start + Duration::from_secs(3)).is_err());
+
+        // a full window refills only up to the burst
+        let later = start + Duration::from_secs(600);
+        for _ in 0..10 {
+            limiter.check(later).unwrap();
+        }
+        assert!(limiter.check(later).is_err());
+    }
+
+    #[test]
+    fn limiter_keeps_to_slow_mode() {
+        let mut limiter = SendLimiter {
+            slow: 30,
+            ..Default::default()
+        };
+        let start = Instant::now();
+
+        limiter.check(start).unwrap();
+        assert_eq!(
+            limiter.check(start + Duration::from_secs(10)),
+            Err(SendError::SlowMode(Duration::from_secs(20)))
+        );
+        limiter.check(start + Duration::from_secs(30)).unwrap();
+
+        limiter.privileged = true;
+        limiter.check(start + Duration::from_secs(31)).unwrap();
+    }
+
+    #[test]
+    fn failing_to_connect_is_reported_in_the_chat() {
+        let rt = Runtime::new().unwrap();
+        let _enter = rt.enter();
+        let listener = rt.block_on(TcpListener::bind("127.0.0.1:0")).unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let chat = Chat::connect(
+            &format!("ws://{}", addr),
+            "viewer",
+            "secret",
+            "somestreamer",
+            Context::default(),
+        );
+
+        wait_for(&chat, |chat| chat.state() == ChatState::Disconnected);
+        match &lines(&chat)[..] {
+            [ChatLine::Notice(notice)] => {
+                assert!(notice.starts_with("Disconnected from chat: "))
+            }
+            lines => panic!("expected a notice, got {:?}", lines),
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde_derive::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io::Write;
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+
+const SERVICE: &str = "streamgui";
+const USER: &str = "twitch";
+
+const NONCE_LEN: usize = 12;
+
+/// Where the access token is kept between runs.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub enum CredentialBackend {
+    /// The OS secret service (Secret Service on Linux, Keychain on macOS, Credential Manager on
+    /// Windows).
+    #[default]
+    Keyring,
+    /// A file encrypted with a key kept in the data directory, for systems without a secret
+    /// service.
+    EncryptedFile,
+}
+
+impl fmt::Display for CredentialBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CredentialBackend::Keyring => write!(f, "System keyring"),
+            CredentialBackend::EncryptedFile => write!(f, "Encrypted file"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CredentialError {
+    Keyring(keyring::Error),
+    Io(std::io::Error),
+    Crypto,
+}
+
+impl fmt::Display for CredentialError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CredentialError::Keyring(e) => write!(f, "keyring error: {}", e),
+            CredentialError::Io(e) => write!(f, "credential file error: {}", e),
+            CredentialError::Crypto => write!(f, "unable to decrypt credential file"),
+        }
+    }
+}
+
+impl From<keyring::Error> for CredentialError {
+    fn from(e: keyring::Error) -> Self {
+        CredentialError::Keyring(e)
+    }
+}
+
+impl From<std::io::Error> for CredentialError {
+    fn from(e: std::io::Error) -> Self {
+        CredentialError::Io(e)
+    }
+}
+
+pub trait CredentialStore {
+    fn get(&self) -> Result<Option<String>, CredentialError>;
+    fn set(&self, token: &str) -> Result<(), CredentialError>;
+    fn delete(&self) -> Result<(), CredentialError>;
+}
+
+/// Open the store for `backend`. `config_dir` holds the encrypted file and `data_dir` its key, so
+/// that copying the config directory elsewhere doesn't carry the token along with it.
+pub fn open(
+    backend: CredentialBackend,
+    config_dir: PathBuf,
+    data_dir: PathBuf,
+) -> Box<dyn CredentialStore> {
+    match backend {
+        CredentialBackend::Keyring => Box::new(KeyringStore),
+        CredentialBackend::EncryptedFile => Box::new(FileStore {
+            path: config_dir.join("credentials.enc"),
+            key_path: data_dir.join("credentials.key"),
+        }),
+    }
+}
+
+pub struct KeyringStore;
+
+impl KeyringStore {
+    fn entry(&self) -> Result<keyring::Entry, CredentialError> {
+        Ok(keyring::Entry::new(SERVICE, USER)?)
+    }
+}
+
+impl CredentialStore for KeyringStore {
+    fn get(&self) -> Result<Option<String>, CredentialError> {
+        match self.entry()?.get_password() {
+            Ok(token) => Ok(Some(token)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn set(&self, token: &str) -> Result<(), CredentialError> {
+        Ok(self.entry()?.set_password(token)?)
+    }
+
+    fn delete(&self) -> Result<(), CredentialError> {
+        match self.entry()?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+pub struct FileStore {
+    path: PathBuf,
+    key_path: PathBuf,
+}
+
+impl FileStore {
+    fn cipher(&self, create: bool) -> Result<Option<ChaCha20Poly1305>, CredentialError> {
+        if self.key_path.exists() {
+            let key = fs::read(&self.key_path)?;
+            if key.len() != 32 {
+                return Err(CredentialError::Crypto);
+            }
+            return Ok(Some(ChaCha20Poly1305::new(Key::from_slice(&key))));
+        }
+
+        if !create {
+            return Ok(None);
+        }
+
+        let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+        write_private(&self.key_path, &key)?;
+        Ok(Some(ChaCha20Poly1305::new(&key)))
+    }
+}
+
+impl CredentialStore for FileStore {
+    fn get(&self) -> Result<Option<String>, CredentialError> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+
+        let Some(cipher) = self.cipher(false)? else {
+            return Err(CredentialError::Crypto);
+        };
+
+        let contents = fs::read(&self.path)?;
+        if contents.len() < NONCE_LEN {
+            return Err(CredentialError::Crypto);
+        }
+        let (nonce, ciphertext) = contents.split_at(NONCE_LEN);
+
+        let token = cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| CredentialError::Crypto)?;
+
+        String::from_utf8(token)
+            .map(Some)
+            .map_err(|_| CredentialError::Crypto)
+    }
+
+    fn set(&self, token: &str) -> Result<(), CredentialError> {
+        let Some(cipher) = self.cipher(true)? else {
+            return Err(CredentialError::Crypto);
+        };
+
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, token.as_bytes())
+            .map_err(|_| CredentialError::Crypto)?;
+
+        let mut contents = nonce.to_vec();
+        contents.extend_from_slice(&ciphertext);
+        write_private(&self.path, &contents)
+    }
+
+    fn delete(&self) -> Result<(), CredentialError> {
+        if self.path.exists() {
+            fs::remove_file(&self.path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Write a file only the current user can read.
+fn write_private(path: &PathBuf, contents: &[u8]) -> Result<(), CredentialError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    let mut file = options.open(path)?;
+    file.write_all(contents)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file_store(dir: &tempfile::TempDir) -> Box<dyn CredentialStore> {
+        open(
+            CredentialBackend::EncryptedFile,
+            dir.path().join("config"),
+            dir.path().join("data"),
+        )
+    }
+
+    #[test]
+    fn file_store_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = file_store(&dir);
+
+        assert_eq!(store.get().unwrap(), None);
+        store.set("first").unwrap();
+        store.set("second").unwrap();
+        assert_eq!(store.get().unwrap().as_deref(), Some("second"));
+
+        // the token isn't in the file as plain text
+        let contents = fs::read(dir.path().join("config/credentials.enc")).unwrap();
+        assert!(!contents.windows(6).any(|w| w == b"second"));
+
+        store.delete().unwrap();
+        assert_eq!(store.get().unwrap(), None);
+        store.delete().unwrap();
+    }
+
+    #[test]
+    fn tampered_file_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = file_store(&dir);
+        let path = dir.path().join("config/credentials.enc");
+
+        store.set("token").unwrap();
+        let mut contents = fs::read(&path).unwrap();
+        let last = contents.len() - 1;
+        contents[last] ^= 1;
+        fs::write(&path, &contents).unwrap();
+        assert!(matches!(store.get(), Err(CredentialError::Crypto)));
+
+        fs::write(&path, [0; 4]).unwrap();
+        assert!(matches!(store.get(), Err(CredentialError::Crypto)));
+    }
+
+    #[test]
+    fn wrong_key_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = file_store(&dir);
+
+        store.set("token").unwrap();
+        fs::write(dir.path().join("data/credentials.key"), [7; 32]).unwrap();
+        assert!(matches!(store.get(), Err(CredentialError::Crypto)));
+
+        fs::remove_file(dir.path().join("data/credentials.key")).unwrap();
+        assert!(matches!(store.get(), Err(CredentialError::Crypto)));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn files_are_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        file_store(&dir).set("token").unwrap();
+
+        for file in ["config/credentials.enc", "data/credentials.key"] {
+            let mode = fs::metadata(dir.path().join(file))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600, "{}", file);
+        }
+    }
+}
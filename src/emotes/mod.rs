@@ -0,0 +1,178 @@
+use crate::irc::Emote;
+use std::collections::HashMap;
+
+/// A piece of a message, either plain text or an emote to show as an image.
+#[derive(Debug, PartialEq)]
+pub enum Run<'a> {
+    Text(&'a str),
+    Emote { id: &'a str, name: &'a str },
+}
+
+/// The image of an emote on the twitch CDN, animated if the emote is. `dark` picks the version
+/// made for a dark background.
+pub fn emote_url(id: &str, dark: bool) -> String {
+    let theme = if dark { "dark" } else { "light" };
+
+    format!(
+        "https://static-cdn.jtvnw.net/emoticons/v2/{}/default/{}/1.0",
+        id, theme
+    )
+}
+
+/// Split `text` into text and the `emotes` of its IRC tags. The ranges count chars rather than
+/// bytes, and must be sorted by where they start, as `parse_emotes` leaves them. Ranges that
+/// overlap an earlier one or run past the end are left as text.
+pub fn split_emotes<'a>(text: &'a str, emotes: &'a [Emote]) -> Vec<Run<'a>> {
+    // byte offset of each char, and of the end
+    let offsets: Vec<usize> = text
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain([text.len()])
+        .collect();
+    let chars = offsets.len() - 1;
+
+    let mut runs = Vec::new();
+    let mut next_char = 0;
+
+    for emote in emotes {
+        if emote.start < next_char || emote.end >= chars || emote.start > emote.end {
+            continue;
+        }
+
+        let start = offsets[emote.start];
+        let end = offsets[emote.end + 1];
+        if emote.start > next_char {
+            runs.push(Run::Text(&text[offsets[next_char]..start]));
+        }
+        runs.push(Run::Emote {
+            id: emote.id.as_str(),
+            name: &text[start..end],
+        });
+        next_char = emote.end + 1;
+    }
+    if next_char < chars {
+        runs.push(Run::Text(&text[offsets[next_char]..]));
+    }
+
+    runs
+}
+
+/// Split `text` into text and the words of it that are names of `known` emotes, mapped to their
+/// ids. For text that comes without emote tags, like stream titles.
+pub fn split_emote_names<'a>(text: &'a str, known: &'a HashMap<String, String>) -> Vec<Run<'a>> {
+    let mut runs = Vec::new();
+    let mut text_start = 0;
+
+    for (start, word) in words(text) {
+        if let Some(id) = known.get(word) {
+            if start > text_start {
+                runs.push(Run::Text(&text[text_start..start]));
+            }
+            runs.push(Run::Emote {
+                id: id.as_str(),
+                name: word,
+            });
+            text_start = start + word.len();
+        }
+    }
+    if text_start < text.len() {
+        runs.push(Run::Text(&text[text_start..]));
+    }
+
+    runs
+}
+
+/// The words of `text` split on whitespace, with the byte offset each starts at.
+fn words(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.split_whitespace()
+        .map(move |word| (word.as_ptr() as usize - text.as_ptr() as usize, word))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::irc::parse_emotes;
+
+    #[test]
+    fn splits_around_emotes() {
+        let emotes = parse_emotes("25:0-4,12-16/1902:6-10");
+
+        assert_eq!(
+            split_emotes("Kappa Keepo Kappa", &emotes),
+            [
+                Run::Emote {
+                    id: "25",
+                    name: "Kappa"
+                },
+                Run::Text(" "),
+                Run::Emote {
+                    id: "1902",
+                    name: "Keepo"
+                },
+                Run::Text(" "),
+                Run::Emote {
+                    id: "25",
+                    name: "Kappa"
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn ranges_count_chars_not_bytes() {
+        // each of the first three is more than one byte, and 👋 is outside the BMP
+        let text = "héé 👋 Kappa ok";
+        let emotes = parse_emotes("25:6-10");
+
+        assert_eq!(
+            split_emotes(text, &emotes),
+            [
+                Run::Text("héé 👋 "),
+                Run::Emote {
+                    id: "25",
+                    name: "Kappa"
+                },
+                Run::Text(" ok"),
+            ]
+        );
+    }
+
+    #[test]
+    fn bad_ranges_are_left_as_text() {
+        let emotes = parse_emotes("25:0-4,2-6,8-40");
+
+        assert_eq!(
+            split_emotes("Kappa hi there", &emotes),
+            [
+                Run::Emote {
+                    id: "25",
+                    name: "Kappa"
+                },
+                Run::Text(" hi there"),
+            ]
+        );
+        assert_eq!(split_emotes("", &emotes), []);
+        assert_eq!(split_emotes("plain", &[]), [Run::Text("plain")]);
+    }
+
+    #[test]
+    fn finds_known_names_in_titles() {
+        let known = HashMap::from([("Kappa".to_owned(), "25".to_owned())]);
+
+        assert_eq!(
+            split_emote_names("ranked grind Kappa  Kappas", &known),
+            [
+                Run::Text("ranked grind "),
+                Run::Emote {
+                    id: "25",
+                    name: "Kappa"
+                },
+                Run::Text("  Kappas"),
+            ]
+        );
+        assert_eq!(
+            split_emote_names("no emotes", &known),
+            [Run::Text("no emotes")]
+        );
+    }
+}
@@ -0,0 +1,263 @@
+use eframe::egui::{ComboBox, TextEdit, Ui};
+use serde_derive::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::fmt;
+use twitch_api::helix::streams::Stream;
+
+/// Order of a stream list.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub enum SortBy {
+    /// The order twitch returned them in.
+    #[default]
+    Default,
+    Viewers,
+    Uptime,
+    Name,
+}
+
+impl fmt::Display for SortBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SortBy::Default => write!(f, "Default"),
+            SortBy::Viewers => write!(f, "Viewers"),
+            SortBy::Uptime => write!(f, "Uptime"),
+            SortBy::Name => write!(f, "Name"),
+        }
+    }
+}
+
+/// Sorting and filtering of one stream list.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StreamFilter {
+    pub sort: SortBy,
+    /// Flip the order, so fewest viewers, shortest uptime or Z to A comes first.
+    pub reverse: bool,
+    /// Only streams whose title contains this, ignoring case.
+    pub title: String,
+    /// Only streams in this language, as a code like `en`.
+    pub language: String,
+    pub hide_mature: bool,
+    /// Only streams that have all of these tags, ignoring case.
+    pub tags: Vec<String>,
+}
+
+/// The filters of each view with a stream list, saved with the config.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ViewFilters {
+    pub streams: StreamFilter,
+    pub followed: StreamFilter,
+    pub category: StreamFilter,
+}
+
+impl StreamFilter {
+    pub fn matches(&self, stream: &Stream) -> bool {
+        let title = self.title.trim().to_lowercase();
+        let language = self.language.trim();
+
+        if !title.is_empty() && !stream.title.to_lowercase().contains(&title) {
+            return false;
+        }
+        if !language.is_empty() && !stream.language.eq_ignore_ascii_case(language) {
+            return false;
+        }
+        if self.hide_mature && stream.is_mature {
+            return false;
+        }
+        self.tags
+            .iter()
+            .all(|tag| stream.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+    }
+
+    /// The streams that match, in the chosen order.
+    pub fn apply(&self, streams: &[Stream]) -> Vec<Stream> {
+        let mut streams: Vec<Stream> = streams
+            .iter()
+            .filter(|stream| self.matches(stream))
+            .cloned()
+            .collect();
+
+        match self.sort {
+            SortBy::Default => {}
+            SortBy::Viewers => streams.sort_by_key(|stream| Reverse(stream.viewer_count)),
+            // started earliest has been live longest
+            SortBy::Uptime => {
+                streams.sort_by(|a, b| a.started_at.as_str().cmp(b.started_at.as_str()))
+            }
+            SortBy::Name => streams.sort_by_key(|stream| stream.user_name.as_str().to_lowercase()),
+        }
+        if self.reverse {
+            streams.reverse();
+        }
+
+        streams
+    }
+
+    /// Add a tag to filter by, unless it is already there.
+    pub fn add_tag(&mut self, tag: &str) {
+        if !self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            self.tags.push(tag.to_owned());
+        }
+    }
+
+    /// The filter bar shown above a stream list. Returns whether the filter should be saved.
+    pub fn show(&mut self, ui: &mut Ui, id: &str) -> bool {
+        let mut changed = false;
+
+        ui.horizontal_wrapped(|ui| {
+            ComboBox::from_id_salt(("sort", id))
+                .selected_text(format!("Sort: {}", self.sort))
+                .show_ui(ui, |ui| {
+                    for option in [
+                        SortBy::Default,
+                        SortBy::Viewers,
+                        SortBy::Uptime,
+                        SortBy::Name,
+                    ] {
+                        changed |= ui
+                            .selectable_value(&mut self.sort, option, option.to_string())
+                            .clicked();
+                    }
+                });
+            changed |= ui.toggle_value(&mut self.reverse, "⇅").changed();
+
+            changed |= ui
+                .add(
+                    TextEdit::singleline(&mut self.title)
+                        .hint_text("Search titles")
+                        .desired_width(120.0),
+                )
+                .lost_focus();
+            changed |= ui
+                .add(
+                    TextEdit::singleline(&mut self.language)
+                        .hint_text("Language")
+                        .desired_width(60.0),
+                )
+                .lost_focus();
+            changed |= ui.checkbox(&mut self.hide_mature, "Hide mature").changed();
+
+            let mut removed = None;
+            for (i, tag) in self.tags.iter().enumerate() {
+                if ui.small_button(format!("{} ✖", tag)).clicked() {
+                    removed = Some(i);
+                }
+            }
+            if let Some(i) = removed {
+                self.tags.remove(i);
+                changed = true;
+            }
+
+            let tag_id = ui.id().with(("new_tag", id));
+            let mut new_tag = ui.data_mut(|d| d.get_temp::<String>(tag_id).unwrap_or_default());
+            let tag_edit = ui.add(
+                TextEdit::singleline(&mut new_tag)
+                    .hint_text("Add tag")
+                    .desired_width(80.0),
+            );
+            if tag_edit.lost_focus() && !new_tag.trim().is_empty() {
+                self.add_tag(new_tag.trim());
+                new_tag.clear();
+                changed = true;
+            }
+            ui.data_mut(|d| d.insert_temp(tag_id, new_tag));
+        });
+
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stream(name: &str, viewers: usize, started_at: &str) -> Stream {
+        serde_json::from_value(serde_json::json!({
+            "id": format!("{}-stream", name),
+            "user_id": format!("{}-id", name),
+            "user_login": name.to_lowercase(),
+            "user_name": name,
+            "game_id": "509658",
+            "game_name": "Just Chatting",
+            "type": "live",
+            "title": format!("{} plays", name),
+            "tags": ["English"],
+            "viewer_count": viewers,
+            "started_at": started_at,
+            "language": "en",
+            "thumbnail_url": "",
+            "tag_ids": [],
+            "is_mature": false
+        }))
+        .unwrap()
+    }
+
+    fn fixtures() -> Vec<Stream> {
+        let mut carol = stream("carol", 50, "2026-10-16T12:00:00Z");
+        carol.language = "de".to_owned();
+        carol.is_mature = true;
+        carol.tags = vec!["Deutsch".to_owned(), "Speedrun".to_owned()];
+
+        vec![
+            stream("Bob", 300, "2026-10-16T15:00:00Z"),
+            stream("alice", 1000, "2026-10-16T18:00:00Z"),
+            carol,
+        ]
+    }
+
+    fn names(streams: &[Stream]) -> Vec<&str> {
+        streams.iter().map(|s| s.user_name.as_str()).collect()
+    }
+
+    #[test]
+    fn default_keeps_api_order() {
+        let filter = StreamFilter::default();
+
+        assert_eq!(names(&filter.apply(&fixtures())), ["Bob", "alice", "carol"]);
+    }
+
+    #[test]
+    fn sorts_by_viewers_uptime_and_name() {
+        let mut filter = StreamFilter {
+            sort: SortBy::Viewers,
+            ..Default::default()
+        };
+        assert_eq!(names(&filter.apply(&fixtures())), ["alice", "Bob", "carol"]);
+
+        filter.sort = SortBy::Uptime;
+        assert_eq!(names(&filter.apply(&fixtures())), ["carol", "Bob", "alice"]);
+
+        filter.sort = SortBy::Name;
+        assert_eq!(names(&filter.apply(&fixtures())), ["alice", "Bob", "carol"]);
+
+        filter.reverse = true;
+        assert_eq!(names(&filter.apply(&fixtures())), ["carol", "Bob", "alice"]);
+    }
+
+    #[test]
+    fn filters_by_language_and_mature_flag() {
+        let mut filter = StreamFilter {
+            language: "DE".to_owned(),
+            ..Default::default()
+        };
+        assert_eq!(names(&filter.apply(&fixtures())), ["carol"]);
+
+        filter.language.clear();
+        filter.hide_mature = true;
+        assert_eq!(names(&filter.apply(&fixtures())), ["Bob", "alice"]);
+    }
+
+    #[test]
+    fn filters_by_tags_and_title() {
+        let mut filter = StreamFilter::default();
+        filter.add_tag("speedrun");
+        filter.add_tag("SPEEDRUN");
+        assert_eq!(filter.tags.len(), 1);
+        assert_eq!(names(&filter.apply(&fixtures())), ["carol"]);
+
+        filter.tags.clear();
+        filter.title = "ALICE".to_owned();
+        assert_eq!(names(&filter.apply(&fixtures())), ["alice"]);
+    }
+}
@@ -0,0 +1,327 @@
+use std::collections::HashMap;
+
+/// One IRC line, with the IRCv3 tags twitch sends ahead of it.
+#[derive(Debug, Default, PartialEq)]
+pub struct Message {
+    pub tags: HashMap<String, String>,
+    /// Where the message came from, like `nick!nick@nick.tmi.twitch.tv`.
+    pub prefix: Option<String>,
+    pub command: String,
+    /// The parameters, with the trailing one (after ` :`) last.
+    pub params: Vec<String>,
+}
+
+impl Message {
+    /// Parse a line without its `\r\n`. `None` if there is no command.
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut message = Message::default();
+        let mut rest = line.trim_end_matches(['\r', '\n']);
+
+        if let Some(tagged) = rest.strip_prefix('@') {
+            let (tags, after) = tagged.split_once(' ')?;
+            message.tags = parse_tags(tags);
+            rest = after.trim_start_matches(' ');
+        }
+        if let Some(prefixed) = rest.strip_prefix(':') {
+            let (prefix, after) = prefixed.split_once(' ')?;
+            message.prefix = Some(prefix.to_owned());
+            rest = after.trim_start_matches(' ');
+        }
+
+        let (command, mut rest) = rest.split_once(' ').unwrap_or((rest, ""));
+        if command.is_empty() {
+            return None;
+        }
+        message.command = command.to_owned();
+
+        loop {
+            rest = rest.trim_start_matches(' ');
+            if rest.is_empty() {
+                break;
+            }
+            if let Some(trailing) = rest.strip_prefix(':') {
+                message.params.push(trailing.to_owned());
+                break;
+            }
+
+            let (param, after) = rest.split_once(' ').unwrap_or((rest, ""));
+            message.params.push(param.to_owned());
+            rest = after;
+        }
+
+        Some(message)
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .get(name)
+            .map(String::as_str)
+            .filter(|value| !value.is_empty())
+    }
+
+    /// The nick from the prefix.
+    pub fn nick(&self) -> Option<&str> {
+        let prefix = self.prefix.as_deref()?;
+
+        Some(prefix.split_once('!').map_or(prefix, |(nick, _)| nick))
+    }
+
+    /// The last parameter, which is the text of PRIVMSG and NOTICE.
+    pub fn trailing(&self) -> Option<&str> {
+        self.params.last().map(String::as_str)
+    }
+}
+
+fn parse_tags(tags: &str) -> HashMap<String, String> {
+    tags.split(';')
+        .filter(|tag| !tag.is_empty())
+        .map(|tag| match tag.split_once('=') {
+            Some((key, value)) => (key.to_owned(), unescape_tag_value(value)),
+            None => (tag.to_owned(), String::new()),
+        })
+        .collect()
+}
+
+/// Undo the escaping of IRCv3 tag values.
+fn unescape_tag_value(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some(':') => unescaped.push(';'),
+            Some('s') => unescaped.push(' '),
+            Some('r') => unescaped.push('\r'),
+            Some('n') => unescaped.push('\n'),
+            Some(other) => unescaped.push(other),
+            // a lone backslash at the end is dropped
+            None => {}
+        }
+    }
+    unescaped
+}
+
+/// A chat badge, like `subscriber/12`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Badge {
+    pub name: String,
+    pub version: String,
+}
+
+/// Where an emote is in a message, as char (not byte) indices, `end` included.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Emote {
+    pub id: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A PRIVMSG, read out of its tags.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChatMessage {
+    pub login: String,
+    pub display_name: String,
+    /// The name color the user picked, if any.
+    pub color: Option<[u8; 3]>,
+    pub badges: Vec<Badge>,
+    /// Sorted by where they start.
+    pub emotes: Vec<Emote>,
+    pub text: String,
+    /// Sent with `/me`.
+    pub action: bool,
+}
+
+impl ChatMessage {
+    /// The chat message in a PRIVMSG, `None` for any other command.
+    pub fn from_message(message: &Message) -> Option<Self> {
+        if message.command != "PRIVMSG" {
+            return None;
+        }
+
+        let login = message.nick()?.to_owned();
+        let mut text = message.trailing()?.to_owned();
+        let mut action = false;
+
+        if let Some(me) = text
+            .strip_prefix("\u{1}ACTION ")
+            .and_then(|t| t.strip_suffix('\u{1}'))
+        {
+            text = me.to_owned();
+            action = true;
+        }
+
+        Some(ChatMessage {
+            display_name: message
+                .tag("display-name")
+                .unwrap_or(login.as_str())
+                .to_owned(),
+            login,
+            color: message.tag("color").and_then(parse_color),
+            badges: message.tag("badges").map(parse_badges).unwrap_or_default(),
+            emotes: message.tag("emotes").map(parse_emotes).unwrap_or_default(),
+            text,
+            action,
+        })
+    }
+}
+
+/// A color like `#1E90FF`.
+pub fn parse_color(color: &str) -> Option<[u8; 3]> {
+    let hex = color.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// Badges like `broadcaster/1,subscriber/12`.
+pub fn parse_badges(badges: &str) -> Vec<Badge> {
+    badges
+        .split(',')
+        .filter_map(|badge| {
+            let (name, version) = badge.split_once('/')?;
+            Some(Badge {
+                name: name.to_owned(),
+                version: version.to_owned(),
+            })
+        })
+        .collect()
+}
+
+/// Emotes like `25:0-4,12-16/1902:6-10`. Ranges that don't parse are skipped.
+pub fn parse_emotes(emotes: &str) -> Vec<Emote> {
+    let mut parsed: Vec<Emote> = emotes
+        .split('/')
+        .filter_map(|emote| emote.split_once(':'))
+        .flat_map(|(id, ranges)| {
+            ranges.split(',').filter_map(move |range| {
+                let (start, end) = range.split_once('-')?;
+                let (start, end) = (start.parse().ok()?, end.parse().ok()?);
+
+                (start <= end).then(|| Emote {
+                    id: id.to_owned(),
+                    start,
+                    end,
+                })
+            })
+        })
+        .collect();
+
+    parsed.sort_by_key(|emote| emote.start);
+    parsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRIVMSG: &str = "@badge-info=subscriber/14;badges=broadcaster/1,subscriber/12;\
+        color=#1E90FF;display-name=SomeStreamer;emotes=25:0-4,12-16/1902:6-10;\
+        id=b34ccfc7;mod=0;room-id=1337;user-id=1337 \
+        :somestreamer!somestreamer@somestreamer.tmi.twitch.tv PRIVMSG #somestreamer \
+        :Kappa Keepo Kappa\r\n";
+
+    #[test]
+    fn parses_tags_prefix_command_and_params() {
+        let message = Message::parse(PRIVMSG).unwrap();
+
+        assert_eq!(message.command, "PRIVMSG");
+        assert_eq!(
+            message.prefix.as_deref(),
+            Some("somestreamer!somestreamer@somestreamer.tmi.twitch.tv")
+        );
+        assert_eq!(message.nick(), Some("somestreamer"));
+        assert_eq!(message.params, ["#somestreamer", "Kappa Keepo Kappa"]);
+        assert_eq!(message.tag("room-id"), Some("1337"));
+        assert_eq!(message.tag("badge-info"), Some("subscriber/14"));
+    }
+
+    #[test]
+    fn parses_lines_without_tags_or_prefix() {
+        let ping = Message::parse("PING :tmi.twitch.tv").unwrap();
+        assert_eq!(ping.command, "PING");
+        assert_eq!(ping.params, ["tmi.twitch.tv"]);
+        assert!(ping.tags.is_empty());
+        assert_eq!(ping.prefix, None);
+
+        let welcome = Message::parse(":tmi.twitch.tv 001 viewer :Welcome, GLHF!").unwrap();
+        assert_eq!(welcome.command, "001");
+        assert_eq!(welcome.params, ["viewer", "Welcome, GLHF!"]);
+
+        assert_eq!(Message::parse(""), None);
+        assert_eq!(Message::parse("@a=b "), None);
+    }
+
+    #[test]
+    fn unescapes_tag_values() {
+        let message = Message::parse(
+            r"@system-msg=5\sraiders\sfrom\:\\here;empty=;flag :tmi.twitch.tv USERNOTICE #c",
+        )
+        .unwrap();
+
+        assert_eq!(message.tag("system-msg"), Some(r"5 raiders from;\here"));
+        assert_eq!(message.tags.get("empty").map(String::as_str), Some(""));
+        assert_eq!(message.tag("empty"), None);
+        assert!(message.tags.contains_key("flag"));
+    }
+
+    #[test]
+    fn reads_chat_messages() {
+        let message = ChatMessage::from_message(&Message::parse(PRIVMSG).unwrap()).unwrap();
+
+        assert_eq!(message.login, "somestreamer");
+        assert_eq!(message.display_name, "SomeStreamer");
+        assert_eq!(message.color, Some([0x1e, 0x90, 0xff]));
+        assert_eq!(
+            message.badges,
+            [
+                Badge {
+                    name: "broadcaster".to_owned(),
+                    version: "1".to_owned()
+                },
+                Badge {
+                    name: "subscriber".to_owned(),
+                    version: "12".to_owned()
+                },
+            ]
+        );
+        let emotes: Vec<(&str, usize, usize)> = message
+            .emotes
+            .iter()
+            .map(|e| (e.id.as_str(), e.start, e.end))
+            .collect();
+        assert_eq!(emotes, [("25", 0, 4), ("1902", 6, 10), ("25", 12, 16)]);
+        assert_eq!(message.text, "Kappa Keepo Kappa");
+        assert!(!message.action);
+    }
+
+    #[test]
+    fn reads_actions_and_missing_tags() {
+        let line = ":viewer!viewer@viewer.tmi.twitch.tv PRIVMSG #c :\u{1}ACTION waves\u{1}";
+        let message = ChatMessage::from_message(&Message::parse(line).unwrap()).unwrap();
+
+        assert_eq!(message.display_name, "viewer");
+        assert_eq!(message.color, None);
+        assert!(message.badges.is_empty());
+        assert!(message.emotes.is_empty());
+        assert_eq!(message.text, "waves");
+        assert!(message.action);
+
+        let notice = Message::parse(":tmi.twitch.tv NOTICE * :Login authentication failed");
+        assert_eq!(ChatMessage::from_message(&notice.unwrap()), None);
+    }
+
+    #[test]
+    fn rejects_bad_colors_and_emote_ranges() {
+        assert_eq!(parse_color("1E90FF"), None);
+        assert_eq!(parse_color("#1E90F"), None);
+        assert_eq!(parse_color("#GGGGGG"), None);
+        assert_eq!(parse_emotes("25:4-0,x-1/:"), []);
+    }
+}
@@ -0,0 +1,140 @@
+use eframe::egui::{Pos2, Rect, pos2};
+use serde_derive::{Deserialize, Serialize};
+use std::fmt;
+
+/// Width over height of a stream thumbnail.
+pub const STREAM_ASPECT: f32 = 16.0 / 9.0;
+/// Width over height of category box art.
+pub const BOX_ART_ASPECT: f32 = 3.0 / 4.0;
+/// Narrowest a grid card gets before a column is dropped.
+pub const MIN_STREAM_CARD_WIDTH: f32 = 240.0;
+pub const MIN_CATEGORY_CARD_WIDTH: f32 = 140.0;
+
+/// How stream and category lists are drawn.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub enum ListLayout {
+    /// One full-width row each.
+    List,
+    /// Cards with large thumbnails, as many columns as fit.
+    #[default]
+    Grid,
+}
+
+impl fmt::Display for ListLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListLayout::List => write!(f, "List"),
+            ListLayout::Grid => write!(f, "Grid"),
+        }
+    }
+}
+
+/// How many cards at least `min_width` wide fit next to each other in `available` width, with
+/// `spacing` between them. Always at least one.
+pub fn grid_columns(available: f32, min_width: f32, spacing: f32) -> usize {
+    let columns = ((available + spacing) / (min_width + spacing)).floor();
+
+    if columns.is_finite() && columns >= 1.0 {
+        columns as usize
+    } else {
+        1
+    }
+}
+
+/// The width of each of `columns` cards filling `available` width.
+pub fn card_width(available: f32, columns: usize, spacing: f32) -> f32 {
+    let columns = columns.max(1) as f32;
+
+    ((available - spacing * (columns - 1.0)) / columns).max(0.0)
+}
+
+/// A twitch thumbnail or box art url template sized for an image `width` pixels wide with the
+/// given aspect. Widths are rounded up to a multiple of 40 so similar sizes share a download.
+pub fn sized_image_url(template: &str, width: f32, aspect: f32) -> String {
+    let width = ((width.max(1.0) / 40.0).ceil() * 40.0) as u32;
+    let height = (width as f32 / aspect).round() as u32;
+
+    template.replace("{width}x{height}", &format!("{}x{}", width, height))
+}
+
+/// Points of a line through `values` filling `rect`, the smallest at the bottom and the largest
+/// at the top. A line through equal values runs along the middle.
+pub fn sparkline_points(values: &[usize], rect: Rect) -> Vec<Pos2> {
+    let (Some(&min), Some(&max)) = (values.iter().min(), values.iter().max()) else {
+        return Vec::new();
+    };
+    let steps = values.len().saturating_sub(1).max(1) as f32;
+
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, &value)| {
+            let height = if max == min {
+                0.5
+            } else {
+                (value - min) as f32 / (max - min) as f32
+            };
+
+            pos2(
+                rect.left() + rect.width() * i as f32 / steps,
+                rect.bottom() - rect.height() * height,
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn columns_follow_the_available_width() {
+        assert_eq!(grid_columns(800.0, 240.0, 8.0), 3);
+        assert_eq!(grid_columns(736.0, 240.0, 8.0), 3);
+        assert_eq!(grid_columns(735.0, 240.0, 8.0), 2);
+        assert_eq!(grid_columns(1920.0, 240.0, 8.0), 7);
+    }
+
+    #[test]
+    fn narrow_windows_still_get_one_column() {
+        assert_eq!(grid_columns(100.0, 240.0, 8.0), 1);
+        assert_eq!(grid_columns(0.0, 240.0, 8.0), 1);
+        assert_eq!(grid_columns(f32::NAN, 240.0, 8.0), 1);
+    }
+
+    #[test]
+    fn cards_fill_the_row() {
+        let width = card_width(800.0, 3, 8.0);
+
+        assert_eq!(width * 3.0 + 8.0 * 2.0, 800.0);
+    }
+
+    #[test]
+    fn sparkline_spans_the_rect() {
+        let rect = Rect::from_min_max(pos2(0.0, 0.0), pos2(100.0, 40.0));
+
+        assert_eq!(
+            sparkline_points(&[10, 30, 20], rect),
+            [pos2(0.0, 40.0), pos2(50.0, 0.0), pos2(100.0, 20.0)]
+        );
+        assert_eq!(
+            sparkline_points(&[5, 5], rect),
+            [pos2(0.0, 20.0), pos2(100.0, 20.0)]
+        );
+        assert!(sparkline_points(&[], rect).is_empty());
+    }
+
+    #[test]
+    fn image_urls_keep_the_aspect() {
+        let template = "https://static-cdn.jtvnw.net/previews-ttv/live_user_x-{width}x{height}.jpg";
+
+        assert_eq!(
+            sized_image_url(template, 250.0, STREAM_ASPECT),
+            "https://static-cdn.jtvnw.net/previews-ttv/live_user_x-280x158.jpg"
+        );
+        assert_eq!(
+            sized_image_url("{width}x{height}", 150.0, BOX_ART_ASPECT),
+            "160x213"
+        );
+    }
+}
@@ -1,37 +1,85 @@
+mod chat;
+mod credentials;
+mod emotes;
+mod filter;
+mod irc;
+mod layout;
+mod notifier;
+mod poller;
 mod server;
+mod streamlink;
 mod twitch;
 
 use crate::TwitchOption::{
-    GetCategoryStreams, GetCategoryStreamsResult, GetFollowedStreams, GetFollowedStreamsResult,
-    GetStreams, GetTopCategories, LoginResult, StreamsResult, TopCategoriesResult,
+    ChannelLiveResult, DeviceCodeResult, FavoriteStreamsResult, FocusedStreamResult,
+    FollowedChannelsResult, GetCategoryStreams, GetCategoryStreamsResult, GetChannelLive,
+    GetFavoriteStreams, GetFocusedStream, GetFollowedChannels, GetFollowedStreams,
+    GetFollowedStreamsResult, GetGlobalEmotes, GetQualities, GetStreams, GetTopCategories,
+    GlobalEmotesResult, LoginResult, QualitiesResult, SearchCategories, SearchCategoriesResult,
+    SearchChannels, SearchChannelsResult, StreamsResult, TopCategoriesResult,
 };
-use crate::server::PORT;
+use crate::chat::{Chat, ChatLine, ChatState, TWITCH_IRC_URL};
+use crate::credentials::{CredentialBackend, CredentialError, CredentialStore};
+use crate::emotes::{Run, emote_url, split_emote_names, split_emotes};
+use crate::filter::{StreamFilter, ViewFilters};
+use crate::layout::{
+    BOX_ART_ASPECT, ListLayout, MIN_CATEGORY_CARD_WIDTH, MIN_STREAM_CARD_WIDTH, STREAM_ASPECT,
+    card_width, grid_columns, sized_image_url, sparkline_points,
+};
+use crate::poller::{AutoRecorder, newly_live};
+use crate::server::{PORT, TokenCapture};
+use crate::streamlink::{COMMON_QUALITIES, StreamLog, StreamlinkConfig, StreamlinkError};
 use crate::twitch::{
-    TwitchError, check_login, get_followed_streams, get_streams, get_top_categories,
+    ChannelSearch, FollowedChannel, OAuthUrls, Page, TwitchError, TwitchSession, device_login,
+    get_all_followed_streams, get_followed_channel_status, get_global_emotes, get_streams,
+    get_streams_for_logins, get_streams_for_users, get_top_categories, login, parse_languages,
+    search_categories, search_channels, uptime,
 };
 use directories_next::ProjectDirs;
+use eframe::egui::scroll_area::ScrollAreaOutput;
 use eframe::egui::{
-    Align, Color32, Context, FontId, Frame, InnerResponse, Label, Layout, RichText, ScrollArea,
-    Sense, Style, TextEdit, Theme, Ui, UiBuilder, Vec2, Widget,
+    Align, Align2, Color32, Context, FontId, Frame, Image, InnerResponse, Label, Layout, RichText,
+    ScrollArea, Sense, Style, TextEdit, Theme, Ui, UiBuilder, Vec2, Widget,
 };
 use eframe::{egui, glow};
 use log::{error, info};
 use serde_derive::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
 use std::fs;
 use std::fs::read_to_string;
 use std::path::PathBuf;
+use std::process::ExitStatus;
+use std::sync::Arc;
 use std::sync::mpsc::{Receiver, Sender};
-use tokio::process::{Child, Command};
+use std::time::{Duration, Instant};
+use tokio::process::Child;
 use tokio::runtime::Runtime;
-use twitch_api::helix::Scope::{ChannelReadSubscriptions, UserReadFollows, UserReadSubscriptions};
+use tokio::task::JoinHandle;
+use twitch_api::helix::Scope;
+use twitch_api::helix::Scope::{
+    ChannelReadSubscriptions, ChatEdit, ChatRead, UserReadFollows, UserReadSubscriptions,
+};
+use twitch_api::helix::chat::GlobalEmote;
+use twitch_api::helix::search::Channel;
 use twitch_api::helix::streams::Stream;
+use twitch_api::twitch_oauth2::id::DeviceCodeResponse;
 use twitch_api::twitch_oauth2::{ClientId, ImplicitUserTokenBuilder};
-use twitch_api::types::{CategoryId, TwitchCategory};
+use twitch_api::types::{CategoryId, TwitchCategory, UserId, UserName};
 use url::Url;
 
 const CLIENT_ID: &str = "ualshng9w0vvyb4w8fql0z4dt3cz8k";
 
+fn login_scopes() -> Vec<Scope> {
+    vec![
+        ChannelReadSubscriptions,
+        ChatEdit,
+        ChatRead,
+        UserReadFollows,
+        UserReadSubscriptions,
+    ]
+}
+
 fn main() {
     env_logger::init();
 
@@ -39,9 +87,6 @@ fn main() {
 
     let _enter = rt.enter();
 
-    // internal server for oauth
-    std::thread::spawn(move || rt.block_on(server::run()));
-
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([800.0, 600.0])
@@ -54,7 +99,13 @@ fn main() {
         Box::new(|cc| {
             egui_extras::install_image_loaders(&cc.egui_ctx);
 
-            let app = App::default();
+            let (token_send, token_recv) = std::sync::mpsc::channel();
+            let token_capture = TokenCapture::new(token_send, cc.egui_ctx.clone());
+
+            // internal server for oauth
+            tokio::spawn(server::run(token_capture.clone()));
+
+            let app = App::new(token_capture, token_recv);
 
             Ok(Box::new(app))
         }),
@@ -64,7 +115,64 @@ fn main() {
 
 #[derive(Deserialize, Serialize)]
 struct AppConfig {
+    #[serde(skip)]
     token: Option<String>,
+    // the token is kept in the credential store, and only written to the file while the store
+    // can't take it so a broken keyring doesn't lose the login. Older configs kept it here too.
+    #[serde(rename = "token", default, skip_serializing_if = "Option::is_none")]
+    plaintext_token: Option<String>,
+    #[serde(default)]
+    credential_backend: CredentialBackend,
+    #[serde(default)]
+    streamlink: StreamlinkConfig,
+    /// Logins of the channels recorded whenever they go live.
+    #[serde(default)]
+    auto_record: Vec<String>,
+    /// Seconds between checks of which followed channels are live.
+    #[serde(default = "default_poll_interval")]
+    poll_interval: u64,
+    /// Show a desktop notification when a followed channel goes live.
+    #[serde(default = "default_notifications")]
+    notifications: bool,
+    /// Logins of the channels that don't get a notification.
+    #[serde(default)]
+    muted: Vec<String>,
+    /// Logins of the channels shown in Favorites, whether followed or not.
+    #[serde(default)]
+    favorites: Vec<String>,
+    #[serde(default)]
+    filters: ViewFilters,
+    /// Only list streams in these languages, as codes like `en`. They are sent with the request
+    /// so every page is full, unlike the filter bar. Empty for any language.
+    #[serde(default)]
+    stream_languages: Vec<String>,
+    #[serde(default)]
+    list_layout: ListLayout,
+    /// Seconds between refreshes of the stream shown in the panel.
+    #[serde(default = "default_focused_refresh_interval")]
+    focused_refresh_interval: u64,
+    /// Show mature streams with a blurred thumbnail.
+    #[serde(default)]
+    blur_mature: bool,
+    /// Show the chat of the focused stream in its panel.
+    #[serde(default = "default_show_chat")]
+    show_chat: bool,
+}
+
+fn default_notifications() -> bool {
+    true
+}
+
+fn default_focused_refresh_interval() -> u64 {
+    30
+}
+
+fn default_show_chat() -> bool {
+    true
+}
+
+fn default_poll_interval() -> u64 {
+    60
 }
 
 impl Default for AppConfig {
@@ -74,7 +182,20 @@ impl Default for AppConfig {
 }
 
 impl AppConfig {
-    fn save(&self) {
+    /// Save the token to the credential store and everything else to the file. If the store
+    /// fails, the token goes in the file instead until a later save succeeds.
+    fn save(&mut self) -> Result<(), CredentialError> {
+        let result = self.save_token();
+
+        self.plaintext_token = match result {
+            Ok(()) => None,
+            Err(_) => self.token.clone(),
+        };
+        self.save_file();
+        result
+    }
+
+    fn save_file(&self) {
         match Self::get_path() {
             Some(path) => {
                 let file_content = toml::to_string(&self).unwrap();
@@ -92,7 +213,19 @@ impl AppConfig {
                 let file_contents = read_to_string(path).expect("failed to read config file");
 
                 match toml::from_str::<AppConfig>(file_contents.as_str()) {
-                    Ok(app_config) => app_config,
+                    Ok(mut app_config) => {
+                        if app_config.plaintext_token.is_some() {
+                            // plaintext token from an older config or a failed save, move it
+                            // into the store
+                            app_config.token = app_config.plaintext_token.clone();
+                            if let Err(e) = app_config.save() {
+                                error!("Unable to move token into credential store: {}", e)
+                            }
+                        } else {
+                            app_config.token = app_config.load_token();
+                        }
+                        app_config
+                    }
                     Err(e) => {
                         panic!("failed to parse config file: {}", e);
                     }
@@ -104,6 +237,47 @@ impl AppConfig {
         }
     }
 
+    fn credential_store(&self) -> Box<dyn CredentialStore> {
+        let proj_dir = Self::project_dirs().expect("Cannot get config path");
+
+        credentials::open(
+            self.credential_backend,
+            proj_dir.config_dir().to_path_buf(),
+            proj_dir.data_dir().to_path_buf(),
+        )
+    }
+
+    fn save_token(&self) -> Result<(), CredentialError> {
+        let store = self.credential_store();
+
+        match &self.token {
+            Some(token) => store.set(token),
+            None => store.delete(),
+        }
+    }
+
+    fn load_token(&self) -> Option<String> {
+        match self.credential_store().get() {
+            Ok(token) => token,
+            Err(e) => {
+                error!("Unable to load token: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Move the token over to `backend` and remove it from the old one.
+    fn set_credential_backend(
+        &mut self,
+        backend: CredentialBackend,
+    ) -> Result<(), CredentialError> {
+        if let Err(e) = self.credential_store().delete() {
+            error!("Unable to remove token from old credential store: {}", e);
+        }
+        self.credential_backend = backend;
+        self.save()
+    }
+
     fn project_dirs() -> Option<ProjectDirs> {
         ProjectDirs::from("com", "porterca", "streamgui")
     }
@@ -141,71 +315,458 @@ enum AppView {
     Categories,
     Streams,
     FollowedLive,
+    FollowedChannels,
+    Favorites,
+    Search,
     Settings,
     CategoryView,
     Watching,
 }
 
 enum TwitchOption {
-    LoginCheck,
-    LoginResult(bool),
-    GetTopCategories(Option<String>),
-    GetStreams(Option<String>),
+    LoginCheck(String),
+    DeviceLogin,
+    DeviceCodeResult(DeviceCodeResponse),
+    LoginResult(Result<TwitchSession, TwitchError>),
+    // paged lists carry the generation of their `Paging`, so a page of a list that has since
+    // been reloaded is dropped
+    GetTopCategories(u64, Option<String>),
+    // languages and pagination
+    GetStreams(u64, Vec<String>, Option<String>),
     GetFollowedStreams,
-    GetCategoryStreams(CategoryId),
-    TopCategoriesResult(Result<Vec<TwitchCategory>, TwitchError>),
-    StreamsResult(Result<Vec<Stream>, TwitchError>),
+    GetCategoryStreams(u64, CategoryId, Vec<String>, Option<String>),
+    TopCategoriesResult(u64, Result<Page<TwitchCategory>, TwitchError>),
+    StreamsResult(u64, Result<Page<Stream>, TwitchError>),
     GetFollowedStreamsResult(Result<Vec<Stream>, TwitchError>),
-    GetCategoryStreamsResult(Result<Vec<Stream>, TwitchError>),
+    GetCategoryStreamsResult(u64, Result<Page<Stream>, TwitchError>),
+    SearchChannels(String, bool),
+    SearchCategories(String),
+    // results carry the search they answer, to drop those of an earlier one
+    SearchChannelsResult(String, bool, Result<ChannelSearch, TwitchError>),
+    SearchCategoriesResult(String, Result<Vec<TwitchCategory>, TwitchError>),
+    GetQualities(StreamlinkConfig, String),
+    QualitiesResult(String, Result<Vec<String>, StreamlinkError>),
+    GetFollowedChannels,
+    FollowedChannelsResult(Result<Vec<FollowedChannel>, TwitchError>),
+    GetFavoriteStreams(Vec<String>),
+    FavoriteStreamsResult(Result<Vec<Stream>, TwitchError>),
+    GetChannelLive(ProcessKey, UserId),
+    ChannelLiveResult(ProcessKey, Result<Option<Stream>, TwitchError>),
+    GetGlobalEmotes,
+    GlobalEmotesResult(Result<Vec<GlobalEmote>, TwitchError>),
+    GetFocusedStream(UserId),
+    FocusedStreamResult(UserId, Result<Option<Stream>, TwitchError>),
 }
 
 struct TwitchMessage {
-    token: Option<String>,
+    session: Option<Arc<TwitchSession>>,
     opt: TwitchOption,
 }
 
+/// Whether a streamlink process plays a stream or writes it to a file.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum ProcessKind {
+    Watch,
+    Record,
+}
+
+/// Identifies a streamlink process, so that a channel can be watched and recorded at once.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct ProcessKey {
+    user_name: String,
+    kind: ProcessKind,
+}
+
+impl fmt::Display for ProcessKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ProcessKind::Watch => write!(f, "{}", self.user_name),
+            ProcessKind::Record => write!(f, "{} (recording)", self.user_name),
+        }
+    }
+}
+
 struct StreamProcess {
     stream: Stream,
+    quality: String,
+    kind: ProcessKind,
+    // file a recording is written to
+    output: Option<PathBuf>,
     process: Child,
+    log: StreamLog,
+    started: Instant,
+    // when the process was asked to terminate, if it was
+    stopping: Option<Instant>,
+    // sent SIGKILL after not stopping in time
+    killed: bool,
+    // for a frame to check on a stopping process, whichever view is open
+    ctx: Context,
+    // start the stream again once the process has stopped
+    restart: bool,
+    reconnect: Reconnect,
+    // started by the auto-recorder rather than the user, and stopped by it too
+    auto_recorded: bool,
+}
+
+/// Reconnects made after a streamlink process dies unexpectedly, when the user opted in.
+#[derive(Clone, Copy, Default)]
+struct Reconnect {
+    enabled: bool,
+    attempts: u32,
+}
+
+const MAX_RECONNECT_ATTEMPTS: u32 = 5;
+
+/// A process that ran at least this long before dying starts over with a fresh set of attempts.
+const RECONNECT_RESET_AFTER: Duration = Duration::from_secs(60);
+
+impl Reconnect {
+    /// Wait before the given attempt, doubling each time up to a minute.
+    fn delay(attempt: u32) -> Duration {
+        Duration::from_secs(5 << attempt.saturating_sub(1).min(4)).min(Duration::from_secs(60))
+    }
+}
+
+/// A stream waiting to be started again after its process died.
+struct ReconnectingStream {
+    stream: Stream,
+    quality: String,
+    kind: ProcessKind,
+    reconnect: Reconnect,
+    auto_recorded: bool,
+    status: ExitStatus,
+    log: StreamLog,
+    retry_at: Instant,
+    // checking the channel is still live before starting streamlink again
+    checking: bool,
+}
+
+/// How long a stopping streamlink process gets to exit before it is killed.
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+/// How often the processes are checked on while waiting for them to exit on close.
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+impl StreamProcess {
+    /// Ask the process to terminate. It is killed by `kill_if_overdue` if it doesn't exit in time.
+    fn stop(&mut self) {
+        use nix::{
+            sys::signal::{Signal::SIGTERM, kill},
+            unistd::Pid,
+        };
+
+        if self.stopping.is_some() {
+            return;
+        }
+        self.stopping = Some(Instant::now());
+        self.ctx.request_repaint_after(STOP_TIMEOUT);
+
+        if let Some(pid) = self.process.id() {
+            if let Err(e) = kill(Pid::from_raw(pid.try_into().expect("invalid pid")), SIGTERM) {
+                error!("Failed to terminate child process: {}", e);
+            }
+        } else if let Err(e) = self.process.start_kill() {
+            error!("Could not kill streamlink child: {}", e);
+        }
+    }
+
+    fn kill_if_overdue(&mut self) {
+        if let Some(stopping) = self.stopping
+            && !self.killed
+            && stopping.elapsed() > STOP_TIMEOUT
+        {
+            self.killed = true;
+            if let Err(e) = self.process.start_kill() {
+                error!("Could not kill streamlink child: {}", e);
+            }
+        }
+    }
+
+    fn state(&self) -> &'static str {
+        match (self.stopping, self.restart) {
+            (Some(_), true) => "Restarting",
+            (Some(_), false) => "Stopping",
+            (None, _) => "Running",
+        }
+    }
+}
+
+/// A stream whose streamlink process exited with an error, or that ended while waiting to
+/// reconnect, kept around so the user can see why it stopped.
+struct FailedStream {
+    stream: Stream,
+    quality: String,
+    kind: ProcessKind,
+    status: ExitStatus,
+    log: StreamLog,
+    // the channel went offline while waiting to reconnect, so it wasn't started again
+    ended: bool,
 }
 
+/// Lines of output shown with a failed stream.
+const FAILED_LOG_LINES: usize = 10;
+
 struct App {
     token: String,
+    session: Option<Arc<TwitchSession>>,
     config: AppConfig,
     login_pending: bool,
+    device_login_pending: bool,
+    device_code: Option<DeviceCodeResponse>,
     current_view: AppView,
     error_message: Option<String>,
     categories: Option<Vec<TwitchCategory>>,
+    categories_paging: Paging,
     streams: Option<Vec<Stream>>,
+    streams_paging: Paging,
     followed_streams: Option<Vec<Stream>>,
+    followed_channels: Option<Vec<FollowedChannel>>,
+    favorite_streams: Option<Vec<Stream>>,
     focused_stream: Option<Stream>,
+    focused_refresh: Option<FocusedRefresh>,
+    // quality for the next launch from the stream panel, instead of the configured one
+    watch_quality: Option<String>,
+    // channel the qualities were asked for, and what streamlink offered once it answered
+    qualities_login: Option<String>,
+    qualities: Option<Vec<String>>,
+    // chat of the focused stream
+    chat: Option<Chat>,
+    chat_input: String,
+    // why the last message couldn't be sent
+    chat_error: Option<String>,
+    // ids of the global emotes by name, for showing them in titles
+    emote_names: HashMap<String, String>,
     focused_category: Option<TwitchCategory>,
     focused_category_streams: Option<Vec<Stream>>,
+    focused_category_paging: Paging,
+    // the stream languages setting as it is being edited
+    stream_languages_input: String,
+    search_query: String,
+    search_live_only: bool,
+    // the query last searched for, which may since have been edited in the text box
+    searched_query: String,
+    searching: bool,
+    search_live_streams: Option<Vec<Stream>>,
+    search_offline_channels: Option<Vec<Channel>>,
+    search_categories: Option<Vec<TwitchCategory>>,
     send: Sender<TwitchMessage>,
     recv: Receiver<TwitchMessage>,
-    active_streams: HashMap<String, StreamProcess>,
+    token_capture: TokenCapture,
+    token_recv: Receiver<String>,
+    active_streams: HashMap<ProcessKey, StreamProcess>,
+    failed_streams: HashMap<ProcessKey, FailedStream>,
+    reconnecting_streams: HashMap<ProcessKey, ReconnectingStream>,
+    poller: Option<JoinHandle<()>>,
+    auto_recorder: AutoRecorder,
 }
 
-impl Default for App {
-    fn default() -> Self {
+impl App {
+    fn new(token_capture: TokenCapture, token_recv: Receiver<String>) -> Self {
+        Self::with_config(AppConfig::default(), token_capture, token_recv)
+    }
+
+    fn with_config(
+        config: AppConfig,
+        token_capture: TokenCapture,
+        token_recv: Receiver<String>,
+    ) -> Self {
         let (send, recv) = std::sync::mpsc::channel();
-        let config = AppConfig::default();
+        // only left in the file when the credential store couldn't take it
+        let error_message = config
+            .plaintext_token
+            .as_ref()
+            .map(|_| "unable to store the login token, keeping it in config.toml".to_owned());
+        let stream_languages_input = config.stream_languages.join(", ");
 
         Self {
             token: config.token.clone().unwrap_or_default(),
+            session: None,
             config,
             login_pending: true,
+            device_login_pending: false,
+            device_code: None,
             current_view: AppView::Login,
-            error_message: None,
+            error_message,
             categories: None,
+            categories_paging: Paging::default(),
             streams: None,
+            streams_paging: Paging::default(),
             followed_streams: None,
+            followed_channels: None,
+            favorite_streams: None,
             focused_stream: None,
+            focused_refresh: None,
+            watch_quality: None,
+            qualities_login: None,
+            qualities: None,
+            chat: None,
+            chat_input: String::new(),
+            chat_error: None,
+            emote_names: HashMap::new(),
             focused_category: None,
             focused_category_streams: None,
+            focused_category_paging: Paging::default(),
+            stream_languages_input,
+            search_query: String::new(),
+            search_live_only: false,
+            searched_query: String::new(),
+            searching: false,
+            search_live_streams: None,
+            search_offline_channels: None,
+            search_categories: None,
             send,
             recv,
+            token_capture,
+            token_recv,
             active_streams: HashMap::new(),
+            failed_streams: HashMap::new(),
+            reconnecting_streams: HashMap::new(),
+            poller: None,
+            auto_recorder: AutoRecorder::default(),
+        }
+    }
+}
+
+const CHAT_EMOTE_SIZE: f32 = 22.0;
+const TITLE_EMOTE_SIZE: f32 = 28.0;
+
+/// Width of the thumbnail requested for a blurred stream, before it is stretched out.
+const BLURRED_THUMBNAIL_WIDTH: f32 = 40.0;
+
+/// What was clicked in a list of streams.
+enum StreamClick {
+    Stream(Box<Stream>),
+    /// One of a stream's tags, to filter the list by.
+    Tag(String),
+}
+
+/// Viewer counts kept for the sparkline of the focused stream.
+const VIEWER_SAMPLES: usize = 120;
+
+/// Keeps the focused stream up to date while its panel is open.
+struct FocusedRefresh {
+    user_id: UserId,
+    /// Viewer counts since the panel opened, oldest first.
+    viewers: Vec<usize>,
+    next_check: Instant,
+    checking: bool,
+    /// The stream was offline at the last check.
+    offline: bool,
+    /// The stream as of the last check that found it live.
+    latest: Option<Stream>,
+}
+
+impl FocusedRefresh {
+    fn new(stream: &Stream, interval: Duration) -> Self {
+        Self {
+            user_id: stream.user_id.clone(),
+            viewers: vec![stream.viewer_count],
+            next_check: Instant::now() + interval,
+            checking: false,
+            offline: false,
+            latest: None,
+        }
+    }
+
+    fn add_viewers(&mut self, viewers: usize) {
+        if self.viewers.len() == VIEWER_SAMPLES {
+            self.viewers.remove(0);
+        }
+        self.viewers.push(viewers);
+    }
+}
+
+/// Room left below the chat messages for the message input.
+const CHAT_INPUT_HEIGHT: f32 = 60.0;
+
+/// Distance from the bottom of a list at which the next page starts loading.
+const NEXT_PAGE_MARGIN: f32 = 300.0;
+
+/// Cursor and loading state of a list that loads more pages as it is scrolled.
+#[derive(Default)]
+struct Paging {
+    cursor: Option<String>,
+    loading: bool,
+    // the last page failed to load, and is only asked for again from the footer
+    failed: bool,
+    // bumped whenever the list starts over, to tell its pages from those of the list before
+    generation: u64,
+}
+
+impl Paging {
+    /// Start over from the first page, returning the generation to send with its request.
+    fn reset(&mut self) -> u64 {
+        self.generation += 1;
+        self.cursor = None;
+        self.loading = true;
+        self.failed = false;
+        self.generation
+    }
+
+    /// The generation and cursor for the next page, unless a page is already loading, the last
+    /// one failed or this was the last one.
+    fn next(&mut self) -> Option<(u64, String)> {
+        if self.loading || self.failed {
+            return None;
+        }
+        let cursor = self.cursor.clone()?;
+        self.loading = true;
+        Some((self.generation, cursor))
+    }
+
+    /// Take the cursor of a page that loaded. `false` if the page belongs to an older list and
+    /// should be dropped.
+    fn finish(&mut self, generation: u64, cursor: Option<String>) -> bool {
+        if generation != self.generation {
+            return false;
+        }
+        self.cursor = cursor;
+        self.loading = false;
+        true
+    }
+
+    /// Note a page that failed to load, keeping the cursor to try it again. `false` if the page
+    /// belongs to an older list.
+    fn fail(&mut self, generation: u64) -> bool {
+        if generation != self.generation {
+            return false;
+        }
+        self.loading = false;
+        self.failed = true;
+        true
+    }
+
+    /// Loading and end-of-list indicators shown below the list, or a retry button after a page
+    /// failed. `true` when retry is clicked, after which the page should be requested again.
+    fn footer(&mut self, ui: &mut Ui) -> bool {
+        ui.vertical_centered(|ui| {
+            if self.loading {
+                ui.spinner();
+            } else if self.failed {
+                if ui.button("Retry").clicked() {
+                    self.failed = false;
+                    return true;
+                }
+            } else if self.cursor.is_none() {
+                ui.weak("End of list");
+            }
+            false
+        })
+        .inner
+    }
+}
+
+fn near_bottom<R>(output: &ScrollAreaOutput<R>) -> bool {
+    output.state.offset.y + output.inner_rect.height() >= output.content_size.y - NEXT_PAGE_MARGIN
+}
+
+/// Add the streams of a later page, skipping any that moved up from an earlier page since it was
+/// loaded.
+fn append_streams(list: &mut Option<Vec<Stream>>, streams: Vec<Stream>) {
+    let list = list.get_or_insert_with(Vec::new);
+
+    for stream in streams {
+        if !list.iter().any(|s| s.id == stream.id) {
+            list.push(stream);
         }
     }
 }
@@ -216,77 +777,398 @@ fn setup_style(style: &mut Style) {
 }
 
 impl App {
+    /// Tell the user when the credential store failed, rather than only logging it, since a saved
+    /// token is then kept in the config file.
+    fn show_credential_error(&mut self, result: Result<(), CredentialError>) {
+        if let Err(e) = result {
+            error!("Unable to save token: {}", e);
+            self.error_message = Some(match self.config.plaintext_token {
+                Some(_) => format!(
+                    "unable to store the login token, keeping it in config.toml: {}",
+                    e
+                ),
+                None => format!("unable to remove the login token: {}", e),
+            });
+        }
+    }
+
     fn logout(&mut self) {
         self.token = "".to_string();
+        self.session = None;
+        self.stop_poller();
+        self.auto_recorder = AutoRecorder::default();
         self.config.token = None;
-        self.config.save();
+        let result = self.config.save();
+        self.show_credential_error(result);
         self.login_pending = false;
         self.current_view = AppView::Login;
         self.categories = None;
+        self.categories_paging = Paging::default();
         self.streams = None;
+        self.streams_paging = Paging::default();
         self.followed_streams = None;
+        self.followed_channels = None;
+        self.favorite_streams = None;
         self.focused_stream = None;
+        self.focused_refresh = None;
+        self.chat = None;
+        self.chat_input.clear();
+        self.chat_error = None;
         self.focused_category = None;
         self.focused_category_streams = None;
+        self.focused_category_paging = Paging::default();
+        self.search_query = String::new();
+        self.searched_query = String::new();
+        self.searching = false;
+        self.search_live_streams = None;
+        self.search_offline_channels = None;
+        self.search_categories = None;
+        self.reconnecting_streams.clear();
+    }
+
+    /// Check which followed channels are live in the background, replacing any earlier poller.
+    fn start_poller(&mut self, ctx: Context) {
+        self.stop_poller();
+
+        let Some(session) = self.session.clone() else {
+            return;
+        };
+        let tx = self.send.clone();
+        let period = Duration::from_secs(self.config.poll_interval.max(10));
+
+        self.poller = Some(poller::spawn(session, period, move |result| {
+            let resp = TwitchMessage {
+                session: None,
+                opt: GetFollowedStreamsResult(result),
+            };
+            if tx.send(resp).is_ok() {
+                ctx.request_repaint();
+            }
+        }));
+    }
+
+    fn stop_poller(&mut self) {
+        if let Some(poller) = self.poller.take() {
+            poller.abort();
+        }
+    }
+
+    /// Start and stop recordings of the channels on the auto-record list as they go live and
+    /// offline.
+    fn auto_record(&mut self, ctx: Context, live: &[Stream]) {
+        // recordings waiting to reconnect count as running, the reconnect starts them again
+        let active: Vec<String> = self
+            .active_streams
+            .values()
+            .map(|stream_process| (stream_process.kind, &stream_process.stream))
+            .chain(
+                self.reconnecting_streams
+                    .values()
+                    .map(|reconnecting| (reconnecting.kind, &reconnecting.stream)),
+            )
+            .filter(|(kind, _)| *kind == ProcessKind::Record)
+            .map(|(_, stream)| stream.user_login.to_string())
+            .collect();
+        let changes = self
+            .auto_recorder
+            .update(&self.config.auto_record, live, &active);
+
+        for stream in changes.start {
+            info!("{} went live, recording", stream.user_login);
+            let quality = self.config.streamlink.quality.clone();
+            self.spawn_stream(
+                ctx.clone(),
+                &stream,
+                quality.as_str(),
+                ProcessKind::Record,
+                Reconnect::default(),
+                true,
+            );
+        }
+
+        // recordings the user started are left alone
+        for login in changes.stop {
+            let recording = self.active_streams.values_mut().find(|stream_process| {
+                stream_process.auto_recorded && stream_process.stream.user_login.as_str() == login
+            });
+
+            if let Some(stream_process) = recording {
+                info!("{} is no longer live, stopping recording", login);
+                stream_process.stop();
+            }
+        }
+    }
+
+    /// Notify about the followed channels that went live since the last check. Nothing is shown
+    /// for the first check, every channel would be new then.
+    fn notify_live(&self, live: &[Stream]) {
+        if !self.config.notifications {
+            return;
+        }
+        let Some(previous) = &self.followed_streams else {
+            return;
+        };
+
+        for stream in newly_live(previous, live) {
+            if self.config.muted.contains(&stream.user_login.to_string()) {
+                continue;
+            }
+            notifier::spawn_notify(
+                format!("{} is live", stream.user_name),
+                format!("{}\n{}", stream.title, stream.game_name),
+            );
+        }
+    }
+
+    fn start_stream(&mut self, ctx: Context, stream: &Stream, quality: &str) {
+        self.spawn_stream(
+            ctx,
+            stream,
+            quality,
+            ProcessKind::Watch,
+            Reconnect::default(),
+            false,
+        );
     }
 
-    fn start_stream(&mut self, stream: &Stream) {
-        let username = stream.user_name.clone();
+    fn record_stream(&mut self, ctx: Context, stream: &Stream, quality: &str) {
+        self.spawn_stream(
+            ctx,
+            stream,
+            quality,
+            ProcessKind::Record,
+            Reconnect::default(),
+            false,
+        );
+    }
+
+    fn spawn_stream(
+        &mut self,
+        ctx: Context,
+        stream: &Stream,
+        quality: &str,
+        kind: ProcessKind,
+        reconnect: Reconnect,
+        auto_recorded: bool,
+    ) {
+        let key = ProcessKey {
+            user_name: stream.user_name.to_string(),
+            kind,
+        };
 
         // check if stream already started
-        if self.active_streams.contains_key(username.as_str()) {
+        if self.active_streams.contains_key(&key) {
             return;
         }
-        let child = Command::new("streamlink")
-            .arg("--twitch-low-latency")
-            .arg(format!("https://twitch.tv/{}", username))
-            .arg("best")
+        self.failed_streams.remove(&key);
+        self.reconnecting_streams.remove(&key);
+
+        let streamlink = &self.config.streamlink;
+        let output = match kind {
+            ProcessKind::Watch => None,
+            ProcessKind::Record => {
+                let dir = streamlink.recording_dir();
+                if let Err(e) = fs::create_dir_all(&dir) {
+                    self.error_message = Some(format!(
+                        "failed to create recording directory {}: {}",
+                        dir.display(),
+                        e
+                    ));
+                    return;
+                }
+                Some(streamlink.recording_path(
+                    stream.user_login.as_str(),
+                    stream.started_at.as_str(),
+                    stream.title.as_str(),
+                ))
+            }
+        };
+
+        let mut child = match streamlink
+            .command(stream.user_login.as_str(), quality, output.as_deref())
             .spawn()
-            .expect("failed to spawn streamlink task");
+        {
+            Ok(child) => child,
+            Err(e) => {
+                self.error_message = Some(format!("failed to start streamlink: {}", e));
+                return;
+            }
+        };
+
+        let log = StreamLog::capture(&mut child, ctx.clone());
 
         let stream_process = StreamProcess {
             stream: stream.clone(),
+            quality: quality.to_owned(),
+            kind,
+            output,
             process: child,
+            log,
+            started: Instant::now(),
+            stopping: None,
+            killed: false,
+            ctx,
+            restart: false,
+            reconnect,
+            auto_recorded,
         };
 
-        self.active_streams
-            .insert(username.to_string(), stream_process);
+        self.active_streams.insert(key, stream_process);
     }
 
-    fn request_streams(&self, ctx: Context) {
+    fn request_streams(&mut self, ctx: Context) {
         info!("Requesting streams");
+        self.streams = None;
+        let generation = self.streams_paging.reset();
+
         let req = TwitchMessage {
-            token: Option::from(self.token.clone()),
-            opt: GetStreams(None),
+            session: self.session.clone(),
+            opt: GetStreams(generation, self.config.stream_languages.clone(), None),
         };
         send_req(req, self.send.clone(), Some(ctx));
     }
 
-    fn request_categories(&self, ctx: Context) {
+    fn request_more_streams(&mut self, ctx: Context) {
+        if let Some((generation, cursor)) = self.streams_paging.next() {
+            let req = TwitchMessage {
+                session: self.session.clone(),
+                opt: GetStreams(
+                    generation,
+                    self.config.stream_languages.clone(),
+                    Some(cursor),
+                ),
+            };
+            send_req(req, self.send.clone(), Some(ctx));
+        }
+    }
+
+    fn request_categories(&mut self, ctx: Context) {
+        self.categories = None;
+        let generation = self.categories_paging.reset();
+
         let req = TwitchMessage {
-            token: Option::from(self.token.clone()),
-            opt: GetTopCategories(None),
+            session: self.session.clone(),
+            opt: GetTopCategories(generation, None),
         };
         send_req(req, self.send.clone(), Some(ctx));
     }
 
-    fn request_category_streams(&self, ctx: Context, category: &TwitchCategory) {
+    fn request_more_categories(&mut self, ctx: Context) {
+        if let Some((generation, cursor)) = self.categories_paging.next() {
+            let req = TwitchMessage {
+                session: self.session.clone(),
+                opt: GetTopCategories(generation, Some(cursor)),
+            };
+            send_req(req, self.send.clone(), Some(ctx));
+        }
+    }
+
+    fn request_category_streams(&mut self, ctx: Context, category: &TwitchCategory) {
+        self.focused_category_streams = None;
+        let generation = self.focused_category_paging.reset();
+
         let req = TwitchMessage {
-            token: Option::from(self.token.clone()),
-            opt: GetCategoryStreams(category.id.clone()),
+            session: self.session.clone(),
+            opt: GetCategoryStreams(
+                generation,
+                category.id.clone(),
+                self.config.stream_languages.clone(),
+                None,
+            ),
         };
         send_req(req, self.send.clone(), Some(ctx));
     }
 
+    fn request_more_category_streams(&mut self, ctx: Context, category: &TwitchCategory) {
+        if let Some((generation, cursor)) = self.focused_category_paging.next() {
+            let req = TwitchMessage {
+                session: self.session.clone(),
+                opt: GetCategoryStreams(
+                    generation,
+                    category.id.clone(),
+                    self.config.stream_languages.clone(),
+                    Some(cursor),
+                ),
+            };
+            send_req(req, self.send.clone(), Some(ctx));
+        }
+    }
+
     fn request_followed(&self, ctx: Option<Context>) {
         let req = TwitchMessage {
-            token: Option::from(self.token.clone()),
+            session: self.session.clone(),
             opt: GetFollowedStreams,
         };
         send_req(req, self.send.clone(), ctx);
     }
 
-    fn build_stream_button(&self, stream: Stream, ui: &mut Ui) -> InnerResponse<()> {
+    fn request_followed_channels(&mut self, ctx: Context) {
+        self.followed_channels = None;
+
+        let req = TwitchMessage {
+            session: self.session.clone(),
+            opt: GetFollowedChannels,
+        };
+        send_req(req, self.send.clone(), Some(ctx));
+    }
+
+    fn request_global_emotes(&mut self, ctx: Context) {
+        let req = TwitchMessage {
+            session: self.session.clone(),
+            opt: GetGlobalEmotes,
+        };
+        send_req(req, self.send.clone(), Some(ctx));
+    }
+
+    fn request_favorites(&mut self, ctx: Context) {
+        self.favorite_streams = None;
+
+        let req = TwitchMessage {
+            session: self.session.clone(),
+            opt: GetFavoriteStreams(self.config.favorites.clone()),
+        };
+        send_req(req, self.send.clone(), Some(ctx));
+    }
+
+    fn request_qualities(&mut self, ctx: Context, user_login: String) {
+        self.qualities_login = Some(user_login.clone());
+        self.qualities = None;
+        self.watch_quality = None;
+
+        let req = TwitchMessage {
+            session: None,
+            opt: GetQualities(self.config.streamlink.clone(), user_login),
+        };
+        send_req(req, self.send.clone(), Some(ctx));
+    }
+
+    fn request_search(&mut self, ctx: Context) {
+        let query = self.search_query.trim().to_owned();
+        if query.is_empty() {
+            return;
+        }
+
+        self.searched_query = query.clone();
+        self.searching = true;
+        self.search_live_streams = None;
+        self.search_offline_channels = None;
+        self.search_categories = None;
+
+        let req = TwitchMessage {
+            session: self.session.clone(),
+            opt: SearchChannels(query.clone(), self.search_live_only),
+        };
+        send_req(req, self.send.clone(), Some(ctx.clone()));
+
+        let req = TwitchMessage {
+            session: self.session.clone(),
+            opt: SearchCategories(query),
+        };
+        send_req(req, self.send.clone(), Some(ctx));
+    }
+
+    /// A stream as a full-width row. The inner response holds the tag clicked, if any.
+    fn build_stream_button(&self, stream: Stream, ui: &mut Ui) -> InnerResponse<Option<String>> {
         ui.scope_builder(
             UiBuilder::new()
                 .id_salt(stream.id.to_string())
@@ -323,13 +1205,27 @@ impl App {
                                 )
                                 .selectable(false)
                                 .ui(ui);
-
-                                let sized_thumbnail =
-                                    stream.thumbnail_url.replace("{width}x{height}", "200x200");
-                                ui.image(sized_thumbnail.as_str());
-                            });
-                        });
-                    });
+                                Label::new(
+                                    RichText::new(format!(
+                                        "live for {}",
+                                        format_duration(uptime(&stream))
+                                    ))
+                                    .size(12.0)
+                                    .weak(),
+                                )
+                                .selectable(false)
+                                .ui(ui);
+                                let clicked_tag =
+                                    stream_badges(ui, &stream, self.has_list_filter());
+
+                                self.stream_thumbnail(&stream, Vec2::splat(200.0)).ui(ui);
+                                clicked_tag
+                            })
+                            .inner
+                        })
+                        .inner
+                    })
+                    .inner
             },
         )
     }
@@ -375,60 +1271,884 @@ impl App {
         )
     }
 
-    fn monitor_children(&mut self) {
-        self.active_streams.retain(
-            |_, stream_process| match stream_process.process.try_wait() {
-                Ok(Some(_status)) => false,
-                Ok(None) => true,
-                Err(_err) => false,
+    /// A stream as a card with a 16:9 thumbnail `width` wide, viewers and uptime drawn over it.
+    /// The inner response holds the tag clicked, if any.
+    fn build_stream_card(
+        &self,
+        stream: &Stream,
+        width: f32,
+        ui: &mut Ui,
+    ) -> InnerResponse<Option<String>> {
+        ui.scope_builder(
+            UiBuilder::new()
+                .id_salt(stream.id.to_string())
+                .sense(Sense::click()),
+            |ui| {
+                let response = ui.response();
+                let visuals = ui.style().interact(&response);
+                let text_color = visuals.text_color();
+
+                Frame::canvas(ui.style())
+                    .fill(visuals.bg_fill)
+                    .stroke(visuals.bg_stroke)
+                    .show(ui, |ui| {
+                        ui.set_width(width);
+
+                        let thumbnail = self
+                            .stream_thumbnail(stream, Vec2::new(width, width / STREAM_ASPECT))
+                            .ui(ui);
+
+                        let overlay = |text: String, align: Align2| {
+                            let pos = align.pos_in_rect(&thumbnail.rect.shrink(4.0));
+                            let painter = ui.painter();
+                            let galley = painter.layout_no_wrap(
+                                text,
+                                FontId::proportional(12.0),
+                                Color32::WHITE,
+                            );
+                            let rect = align.anchor_size(pos, galley.size()).expand(2.0);
+
+                            painter.rect_filled(rect, 2.0, Color32::from_black_alpha(180));
+                            painter.galley(rect.min + Vec2::splat(2.0), galley, Color32::WHITE);
+                        };
+                        overlay(format!("👁 {}", stream.viewer_count), Align2::LEFT_BOTTOM);
+                        overlay(format_duration(uptime(stream)), Align2::RIGHT_BOTTOM);
+
+                        Label::new(
+                            RichText::new(stream.user_name.as_str())
+                                .color(text_color)
+                                .size(16.0),
+                        )
+                        .selectable(false)
+                        .truncate()
+                        .ui(ui);
+                        Label::new(
+                            RichText::new(stream.title.as_str())
+                                .color(text_color)
+                                .size(12.0),
+                        )
+                        .selectable(false)
+                        .truncate()
+                        .ui(ui);
+                        Label::new(RichText::new(stream.game_name.as_str()).size(12.0).weak())
+                            .selectable(false)
+                            .truncate()
+                            .ui(ui);
+
+                        stream_badges(ui, stream, self.has_list_filter())
+                    })
+                    .inner
             },
-        );
+        )
     }
-}
 
-impl eframe::App for App {
+    /// The thumbnail of `stream` at `size`, blurred if it is mature and that is turned on.
+    fn stream_thumbnail(&self, stream: &Stream, size: Vec2) -> Image<'static> {
+        // a tiny thumbnail stretched out is blurry enough
+        let width = if self.config.blur_mature && stream.is_mature {
+            BLURRED_THUMBNAIL_WIDTH
+        } else {
+            size.x
+        };
+
+        Image::new(sized_image_url(
+            stream.thumbnail_url.as_str(),
+            width,
+            size.x / size.y,
+        ))
+        .fit_to_exact_size(size)
+    }
+
+    /// Whether the current view has a list filter that tags can be added to.
+    fn has_list_filter(&self) -> bool {
+        matches!(
+            self.current_view,
+            AppView::Streams | AppView::FollowedLive | AppView::CategoryView
+        )
+    }
+
+    /// The filter of the list in the current view, if it has one.
+    fn current_filter(&mut self) -> Option<&mut StreamFilter> {
+        match self.current_view {
+            AppView::Streams => Some(&mut self.config.filters.streams),
+            AppView::FollowedLive => Some(&mut self.config.filters.followed),
+            AppView::CategoryView => Some(&mut self.config.filters.category),
+            _ => None,
+        }
+    }
+
+    /// Only show streams with `tag` in the current list.
+    fn filter_by_tag(&mut self, tag: &str) {
+        if let Some(filter) = self.current_filter() {
+            filter.add_tag(tag);
+            self.config.save_file();
+        }
+    }
+
+    fn handle_stream_click(&mut self, click: Option<StreamClick>) {
+        match click {
+            Some(StreamClick::Stream(stream)) => self.focused_stream = Some(*stream),
+            Some(StreamClick::Tag(tag)) => self.filter_by_tag(&tag),
+            None => {}
+        }
+    }
+
+    /// A category as a card with its box art `width` wide.
+    fn build_category_card(
+        &self,
+        category: &TwitchCategory,
+        width: f32,
+        ui: &mut Ui,
+    ) -> InnerResponse<()> {
+        ui.scope_builder(
+            UiBuilder::new()
+                .id_salt(category.id.to_string())
+                .sense(Sense::click()),
+            |ui| {
+                let response = ui.response();
+                let visuals = ui.style().interact(&response);
+                let text_color = visuals.text_color();
+
+                Frame::canvas(ui.style())
+                    .fill(visuals.bg_fill)
+                    .stroke(visuals.bg_stroke)
+                    .show(ui, |ui| {
+                        ui.set_width(width);
+
+                        Image::new(sized_image_url(
+                            category.box_art_url.as_str(),
+                            width,
+                            BOX_ART_ASPECT,
+                        ))
+                        .fit_to_exact_size(Vec2::new(width, width / BOX_ART_ASPECT))
+                        .ui(ui);
+
+                        Label::new(
+                            RichText::new(category.name.as_str())
+                                .color(text_color)
+                                .size(14.0),
+                        )
+                        .selectable(false)
+                        .truncate()
+                        .ui(ui);
+                    });
+            },
+        )
+    }
+
+    /// Draw `streams` in the configured layout, returning what was clicked.
+    fn show_streams(&self, streams: &[Stream], ui: &mut Ui) -> Option<StreamClick> {
+        let mut clicked = None;
+        let mut on_click = |built: InnerResponse<Option<String>>, stream: &Stream| {
+            if let Some(tag) = built.inner {
+                clicked = Some(StreamClick::Tag(tag));
+            } else if built.response.clicked() {
+                clicked = Some(StreamClick::Stream(Box::new(stream.clone())));
+            }
+        };
+
+        match self.config.list_layout {
+            ListLayout::List => {
+                for stream in streams {
+                    on_click(self.build_stream_button(stream.clone(), ui), stream);
+                }
+            }
+            ListLayout::Grid => {
+                show_grid(ui, streams, MIN_STREAM_CARD_WIDTH, |ui, stream, width| {
+                    on_click(self.build_stream_card(stream, width, ui), stream);
+                });
+            }
+        }
+
+        clicked
+    }
+
+    /// Draw `categories` in the configured layout, returning the one clicked.
+    fn show_categories(
+        &self,
+        categories: &[TwitchCategory],
+        ui: &mut Ui,
+    ) -> Option<TwitchCategory> {
+        let mut clicked = None;
+
+        match self.config.list_layout {
+            ListLayout::List => {
+                for category in categories {
+                    if self
+                        .build_category_button(category.clone(), ui)
+                        .response
+                        .clicked()
+                    {
+                        clicked = Some(category.clone());
+                    }
+                }
+            }
+            ListLayout::Grid => {
+                show_grid(
+                    ui,
+                    categories,
+                    MIN_CATEGORY_CARD_WIDTH,
+                    |ui, category, width| {
+                        if self
+                            .build_category_card(category, width, ui)
+                            .response
+                            .clicked()
+                        {
+                            clicked = Some(category.clone());
+                        }
+                    },
+                );
+            }
+        }
+
+        clicked
+    }
+
+    /// The chat of the focused stream, newest messages at the bottom.
+    fn show_chat(&mut self, ui: &mut Ui) {
+        let Some(state) = self.chat.as_ref().map(Chat::state) else {
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            ui.heading("Chat");
+            match state {
+                ChatState::Connecting => {
+                    ui.spinner();
+                }
+                ChatState::Joined => {}
+                ChatState::Disconnected => {
+                    // connects again on the next frame
+                    if ui.button("Reconnect").clicked() {
+                        self.chat = None;
+                    }
+                }
+            }
+        });
+        let Some(chat) = &self.chat else {
+            return;
+        };
+
+        ScrollArea::vertical()
+            .id_salt("chat")
+            .stick_to_bottom(true)
+            .auto_shrink(false)
+            .max_height(ui.available_height() - CHAT_INPUT_HEIGHT)
+            .show(ui, |ui| {
+                chat.with_lines(|lines| {
+                    for line in lines {
+                        match line {
+                            ChatLine::Message(message) => {
+                                ui.horizontal_wrapped(|ui| {
+                                    ui.spacing_mut().item_spacing.x = 4.0;
+
+                                    for badge in &message.badges {
+                                        if let Some(symbol) = badge_symbol(badge.name.as_str()) {
+                                            ui.label(symbol).on_hover_text(badge.name.as_str());
+                                        }
+                                    }
+                                    let name =
+                                        RichText::new(message.display_name.as_str()).strong();
+                                    let name = match message.color {
+                                        Some([r, g, b]) => name.color(Color32::from_rgb(r, g, b)),
+                                        None => name,
+                                    };
+                                    ui.label(name);
+
+                                    let runs = split_emotes(&message.text, &message.emotes);
+                                    show_runs(ui, &runs, CHAT_EMOTE_SIZE, |text| {
+                                        let text = RichText::new(text);
+                                        if message.action { text.italics() } else { text }
+                                    });
+                                });
+                            }
+                            ChatLine::Notice(notice) => {
+                                ui.weak(RichText::new(notice).italics());
+                            }
+                            ChatLine::Error(error) => {
+                                ui.colored_label(Color32::RED, error);
+                            }
+                        }
+                    }
+                });
+            });
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            let input = ui.add(
+                TextEdit::singleline(&mut self.chat_input)
+                    .hint_text("Send a message")
+                    .desired_width(ui.available_width() - 50.0),
+            );
+            let entered = input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+            let send_clicked = ui.button("Send").clicked();
+
+            if (entered || send_clicked) && !self.chat_input.trim().is_empty() {
+                match chat.send(self.chat_input.trim()) {
+                    Ok(()) => {
+                        self.chat_input.clear();
+                        self.chat_error = None;
+                    }
+                    Err(e) => self.chat_error = Some(e.to_string()),
+                }
+                input.request_focus();
+            }
+        });
+        if let Some(error) = &self.chat_error {
+            ui.colored_label(Color32::RED, error);
+        }
+    }
+
+    fn focused_refresh_interval(&self) -> Duration {
+        Duration::from_secs(self.config.focused_refresh_interval.max(5))
+    }
+
+    /// Ask for the focused stream again once the refresh interval has passed.
+    fn check_focused_stream(&mut self, ctx: &Context) {
+        let Some(stream) = &self.focused_stream else {
+            self.focused_refresh = None;
+            return;
+        };
+        let interval = self.focused_refresh_interval();
+
+        let refresh = match &mut self.focused_refresh {
+            Some(refresh) if refresh.user_id == stream.user_id => refresh,
+            _ => self
+                .focused_refresh
+                .insert(FocusedRefresh::new(stream, interval)),
+        };
+        // picking the stream again from a list hands back the list's older copy
+        if let Some(latest) = &refresh.latest
+            && latest != stream
+        {
+            self.focused_stream = Some(latest.clone());
+        }
+        if refresh.checking {
+            return;
+        }
+
+        let now = Instant::now();
+        if refresh.next_check > now {
+            ctx.request_repaint_after(refresh.next_check - now);
+            return;
+        }
+
+        refresh.checking = true;
+        let req = TwitchMessage {
+            session: self.session.clone(),
+            opt: GetFocusedStream(refresh.user_id.clone()),
+        };
+        send_req(req, self.send.clone(), Some(ctx.clone()));
+    }
+
+    /// Check whether streams waiting to reconnect are still live once their wait is over.
+    fn check_reconnects(&mut self, ctx: &Context) {
+        let now = Instant::now();
+
+        for (key, reconnecting) in self.reconnecting_streams.iter_mut() {
+            if reconnecting.checking {
+                continue;
+            }
+            if reconnecting.retry_at > now {
+                ctx.request_repaint_after(reconnecting.retry_at - now);
+                continue;
+            }
+
+            reconnecting.checking = true;
+            let req = TwitchMessage {
+                session: self.session.clone(),
+                opt: GetChannelLive(key.clone(), reconnecting.stream.user_id.clone()),
+            };
+            send_req(req, self.send.clone(), Some(ctx.clone()));
+        }
+    }
+
+    /// Schedule another reconnect attempt, or give up once they have run out.
+    fn retry_reconnect(&mut self, key: ProcessKey, mut reconnecting: ReconnectingStream) {
+        if reconnecting.reconnect.attempts >= MAX_RECONNECT_ATTEMPTS {
+            self.error_message = Some(format!(
+                "{} stopped: gave up reconnecting after {} attempts",
+                key, reconnecting.reconnect.attempts
+            ));
+            self.failed_streams.insert(
+                key,
+                FailedStream {
+                    stream: reconnecting.stream,
+                    quality: reconnecting.quality,
+                    kind: reconnecting.kind,
+                    status: reconnecting.status,
+                    log: reconnecting.log,
+                    ended: false,
+                },
+            );
+            return;
+        }
+
+        reconnecting.reconnect.attempts += 1;
+        reconnecting.retry_at = Instant::now() + Reconnect::delay(reconnecting.reconnect.attempts);
+        reconnecting.checking = false;
+        self.reconnecting_streams.insert(key, reconnecting);
+    }
+
+    /// Clean up after streamlink processes that exited. Returns the processes to start again.
+    fn monitor_children(&mut self) -> Vec<StreamProcess> {
+        let mut exited = Vec::new();
+        let mut restarts = Vec::new();
+
+        for (key, stream_process) in self.active_streams.iter_mut() {
+            match stream_process.process.try_wait() {
+                Ok(Some(status)) => exited.push((key.clone(), Some(status))),
+                Ok(None) => stream_process.kill_if_overdue(),
+                Err(_err) => exited.push((key.clone(), None)),
+            }
+        }
+
+        for (key, status) in exited {
+            let Some(stream_process) = self.active_streams.remove(&key) else {
+                continue;
+            };
+
+            if stream_process.restart {
+                restarts.push(stream_process);
+            } else if let Some(status) = status
+                && stream_process.reconnect.enabled
+                && stream_process.stopping.is_none()
+                // a player that exits cleanly was closed by the user, while a recording that
+                // does may only have lost the stream
+                && !(status.success() && stream_process.kind == ProcessKind::Watch)
+            {
+                let mut reconnect = stream_process.reconnect;
+                if stream_process.started.elapsed() >= RECONNECT_RESET_AFTER {
+                    reconnect.attempts = 0;
+                }
+                info!("{} exited with {}, reconnecting", key, status);
+
+                self.retry_reconnect(
+                    key,
+                    ReconnectingStream {
+                        stream: stream_process.stream,
+                        quality: stream_process.quality,
+                        kind: stream_process.kind,
+                        reconnect,
+                        auto_recorded: stream_process.auto_recorded,
+                        status,
+                        log: stream_process.log,
+                        retry_at: Instant::now(),
+                        checking: false,
+                    },
+                );
+            } else if let Some(status) = status
+                && !status.success()
+                && stream_process.stopping.is_none()
+            {
+                self.error_message = Some(format!("{} stopped: {}", key, status));
+                self.failed_streams.insert(
+                    key,
+                    FailedStream {
+                        stream: stream_process.stream,
+                        quality: stream_process.quality,
+                        kind: stream_process.kind,
+                        status,
+                        log: stream_process.log,
+                        ended: false,
+                    },
+                );
+            }
+        }
+
+        restarts
+    }
+}
+
+/// The sidebar button of the Watching view, like "Watching 1 (2 stopped)", counting the running
+/// streams and those waiting on the user or a reconnect. `None` when there is nothing to show.
+fn watching_label(running: usize, reconnecting: usize, stopped: usize) -> Option<String> {
+    if running + reconnecting + stopped == 0 {
+        return None;
+    }
+
+    let mut waiting = Vec::new();
+    if reconnecting > 0 {
+        waiting.push(format!("{} reconnecting", reconnecting));
+    }
+    if stopped > 0 {
+        waiting.push(format!("{} stopped", stopped));
+    }
+
+    Some(if waiting.is_empty() {
+        format!("Watching {}", running)
+    } else {
+        format!("Watching {} ({})", running, waiting.join(", "))
+    })
+}
+
+/// A file size for display, like "1.2 GB".
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+
+    if bytes < 1000 {
+        return format!("{} B", bytes);
+    }
+
+    let mut size = bytes as f64 / 1000.0;
+    let mut unit = 0;
+    while size >= 1000.0 && unit < UNITS.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// Lay `items` out in rows of as many cards as fit, each at least `min_width` wide. `card` draws
+/// one item given the inner width of its card.
+fn show_grid<T>(ui: &mut Ui, items: &[T], min_width: f32, mut card: impl FnMut(&mut Ui, &T, f32)) {
+    let spacing = ui.spacing().item_spacing.x;
+    let available = ui.available_width();
+    let columns = grid_columns(available, min_width, spacing);
+    let width = card_width(available, columns, spacing);
+    // leave room for the frame around each card, at its widest when hovered
+    let frame = Frame::canvas(ui.style()).stroke(ui.style().visuals.widgets.hovered.bg_stroke);
+    let inner_width = width - frame.total_margin().sum().x;
+
+    for row in items.chunks(columns) {
+        ui.horizontal_top(|ui| {
+            for item in row {
+                ui.allocate_ui(Vec2::new(width, 0.0), |ui| card(ui, item, inner_width));
+            }
+        });
+    }
+}
+
+/// The language, mature marker and tags of a stream. Returns the tag clicked, when `clickable`.
+fn stream_badges(ui: &mut Ui, stream: &Stream, clickable: bool) -> Option<String> {
+    let mut clicked = None;
+
+    ui.horizontal_wrapped(|ui| {
+        ui.spacing_mut().item_spacing.x = 4.0;
+
+        if !stream.language.is_empty() {
+            let language = RichText::new(stream.language.to_uppercase())
+                .small()
+                .strong()
+                .background_color(ui.visuals().faint_bg_color);
+            ui.label(language).on_hover_text("Language");
+        }
+        if stream.is_mature {
+            let mature = RichText::new("18+").small().strong().color(Color32::RED);
+            ui.label(mature).on_hover_text("Mature content");
+        }
+        for tag in &stream.tags {
+            if !clickable {
+                ui.label(RichText::new(tag).small().weak());
+            } else if ui
+                .small_button(tag)
+                .on_hover_text("Only show streams with this tag")
+                .clicked()
+            {
+                clicked = Some(tag.clone());
+            }
+        }
+    });
+
+    clicked
+}
+
+/// A small line of how the viewer count went.
+fn viewer_sparkline(ui: &mut Ui, viewers: &[usize]) {
+    let (rect, _) = ui.allocate_exact_size(
+        Vec2::new(ui.available_width().min(200.0), 32.0),
+        Sense::hover(),
+    );
+    let color = ui.visuals().widgets.noninteractive.fg_stroke.color;
+
+    ui.painter().add(egui::Shape::line(
+        sparkline_points(viewers, rect.shrink(2.0)),
+        egui::Stroke::new(1.5, color),
+    ));
+}
+
+/// Show text with emotes in it, `text` styling the parts in between.
+fn show_runs(ui: &mut Ui, runs: &[Run], emote_size: f32, text: impl Fn(&str) -> RichText) {
+    let dark = ui.visuals().dark_mode;
+
+    for run in runs {
+        match run {
+            Run::Text(part) => {
+                ui.label(text(part));
+            }
+            Run::Emote { id, name } => {
+                Image::new(emote_url(id, dark))
+                    .fit_to_exact_size(Vec2::splat(emote_size))
+                    .ui(ui)
+                    .on_hover_text(*name);
+            }
+        }
+    }
+}
+
+/// A symbol for the chat badges everyone knows, `None` for the rest.
+fn badge_symbol(badge: &str) -> Option<&'static str> {
+    match badge {
+        "broadcaster" => Some("🎥"),
+        "moderator" => Some("🗡"),
+        "vip" => Some("💎"),
+        "subscriber" | "founder" => Some("★"),
+        "staff" | "admin" | "global_mod" => Some("🔧"),
+        _ => None,
+    }
+}
+
+/// A short duration for display, like "2h 14m" or "5m 10s".
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+
+    if secs >= 3600 {
+        format!("{}h {}m", secs / 3600, secs % 3600 / 60)
+    } else {
+        format!("{}m {}s", secs / 60, secs % 60)
+    }
+}
+
+impl eframe::App for App {
     fn update(&mut self, ctx: &Context, _frame: &mut eframe::Frame) {
-        self.monitor_children();
+        for stream_process in self.monitor_children() {
+            self.spawn_stream(
+                ctx.clone(),
+                &stream_process.stream,
+                stream_process.quality.as_str(),
+                stream_process.kind,
+                stream_process.reconnect,
+                stream_process.auto_recorded,
+            );
+        }
+        self.check_reconnects(ctx);
+        self.check_focused_stream(ctx);
+
+        if let Ok(token) = self.token_recv.try_recv() {
+            // token sent back by the browser, log in with it as if it had been pasted
+            self.token = token;
+            let req = TwitchMessage {
+                session: None,
+                opt: TwitchOption::LoginCheck(self.token.clone()),
+            };
+            send_req(req, self.send.clone(), Some(ctx.clone()));
+        }
 
         if let Ok(added) = self.recv.try_recv() {
             let TwitchMessage { opt, .. } = added;
             match opt {
+                DeviceCodeResult(code) => {
+                    self.device_code = Some(code);
+                }
                 LoginResult(result) => {
-                    if let AppView::Login = self.current_view {
-                        if result {
-                            self.error_message = None;
-                            self.current_view = AppView::FollowedLive;
-                            self.config.token = Some(self.token.clone());
-                            self.config.save();
+                    self.device_login_pending = false;
+                    self.device_code = None;
 
-                            self.request_followed(Some(ctx.clone()))
-                        } else {
-                            self.error_message = Option::from("login failed".to_owned());
+                    if let AppView::Login = self.current_view {
+                        match result {
+                            Ok(session) => {
+                                self.token = session.access_token();
+                                self.session = Some(Arc::new(session));
+                                self.error_message = None;
+                                self.current_view = AppView::FollowedLive;
+                                self.config.token = Some(self.token.clone());
+                                let result = self.config.save();
+                                self.show_credential_error(result);
+
+                                self.request_followed(Some(ctx.clone()));
+                                self.start_poller(ctx.clone());
+                                self.request_global_emotes(ctx.clone());
+                            }
+                            Err(TwitchError::DeviceCode(e)) => {
+                                self.error_message = Some(format!("login failed: {}", e));
+                            }
+                            Err(_) => {
+                                self.error_message = Option::from("login failed".to_owned());
+                            }
                         }
                     }
                 }
-                TopCategoriesResult(result) => match result {
-                    Ok(categories) => {
-                        self.categories = Some(categories);
-                        self.error_message = None;
+                TopCategoriesResult(generation, result) => match result {
+                    Ok(page) => {
+                        if self.categories_paging.finish(generation, page.cursor) {
+                            self.categories
+                                .get_or_insert_with(Vec::new)
+                                .extend(page.data);
+                            self.error_message = None;
+                        }
+                    }
+                    Err(e) => {
+                        if self.categories_paging.fail(generation) {
+                            self.error_message = Some(match e {
+                                TwitchError::Client(cre) => cre.to_string(),
+                                _ => e.to_string(),
+                            });
+                        }
                     }
-                    Err(e) => match e {
-                        TwitchError::Client(cre) => {
-                            self.error_message = Some(cre.to_string());
+                },
+                StreamsResult(generation, result) => match result {
+                    Ok(page) => {
+                        if self.streams_paging.finish(generation, page.cursor) {
+                            append_streams(&mut self.streams, page.data);
                         }
-                        _ => {
+                    }
+                    Err(e) => {
+                        if self.streams_paging.fail(generation) {
                             self.error_message = Some(e.to_string());
                         }
-                    },
+                    }
+                },
+                GetFollowedStreamsResult(result) => match result {
+                    Ok(streams) => {
+                        self.auto_record(ctx.clone(), &streams);
+                        self.notify_live(&streams);
+                        self.followed_streams = Some(streams);
+                    }
+                    Err(e) => {
+                        self.error_message = Some(e.to_string());
+                    }
+                },
+                FollowedChannelsResult(result) => match result {
+                    Ok(channels) => {
+                        self.followed_channels = Some(channels);
+                    }
+                    Err(e) => {
+                        self.followed_channels = Some(Vec::new());
+                        self.error_message = Some(e.to_string());
+                    }
                 },
-                StreamsResult(result) => {
-                    self.streams = Some(result.unwrap());
+                GlobalEmotesResult(result) => match result {
+                    Ok(emotes) => {
+                        self.emote_names = emotes
+                            .into_iter()
+                            .map(|emote| (emote.name, emote.id.to_string()))
+                            .collect();
+                    }
+                    // titles just show emote names as text
+                    Err(e) => error!("Unable to get global emotes: {}", e),
+                },
+                FavoriteStreamsResult(result) => match result {
+                    Ok(streams) => {
+                        self.favorite_streams = Some(streams);
+                    }
+                    Err(e) => {
+                        self.favorite_streams = Some(Vec::new());
+                        self.error_message = Some(e.to_string());
+                    }
+                },
+                GetCategoryStreamsResult(generation, result) => match result {
+                    Ok(page) => {
+                        if self.focused_category_paging.finish(generation, page.cursor) {
+                            append_streams(&mut self.focused_category_streams, page.data);
+                        }
+                    }
+                    Err(e) => {
+                        if self.focused_category_paging.fail(generation) {
+                            self.error_message = Some(e.to_string());
+                        }
+                    }
+                },
+                SearchChannelsResult(query, live_only, result) => {
+                    if query == self.searched_query && live_only == self.search_live_only {
+                        match result {
+                            Ok(search) => {
+                                self.search_live_streams = Some(search.live);
+                                self.search_offline_channels = Some(search.offline);
+                            }
+                            Err(e) => {
+                                self.search_live_streams = Some(Vec::new());
+                                self.search_offline_channels = Some(Vec::new());
+                                self.error_message = Some(e.to_string());
+                            }
+                        }
+                        self.searching = self.search_categories.is_none();
+                    }
                 }
-                GetFollowedStreamsResult(result) => {
-                    self.followed_streams = Some(result.unwrap());
+                QualitiesResult(user_login, result) => {
+                    // ignore answers for a stream that is no longer focused
+                    if self.qualities_login.as_ref() == Some(&user_login) {
+                        match result {
+                            Ok(qualities) => {
+                                self.qualities = Some(qualities);
+                            }
+                            Err(e) => {
+                                self.qualities = Some(COMMON_QUALITIES.map(str::to_owned).to_vec());
+                                self.error_message = Some(e.to_string());
+                            }
+                        }
+                    }
+                }
+                FocusedStreamResult(user_id, result) => {
+                    let next_check = Instant::now() + self.focused_refresh_interval();
+
+                    // the panel may have moved on to another stream in the meantime
+                    if let Some(refresh) = &mut self.focused_refresh
+                        && refresh.user_id == user_id
+                    {
+                        refresh.checking = false;
+                        refresh.next_check = next_check;
+
+                        match result {
+                            Ok(Some(stream)) => {
+                                refresh.add_viewers(stream.viewer_count);
+                                refresh.offline = false;
+                                refresh.latest = Some(stream.clone());
+                                self.focused_stream = Some(stream);
+                            }
+                            Ok(None) => refresh.offline = true,
+                            Err(e) => error!("Unable to refresh {}: {}", user_id, e),
+                        }
+                    }
                 }
-                GetCategoryStreamsResult(result) => {
-                    self.focused_category_streams = Some(result.unwrap());
+                ChannelLiveResult(key, result) => {
+                    // the wait may have been cancelled in the meantime
+                    if let Some(reconnecting) = self.reconnecting_streams.remove(&key) {
+                        match result {
+                            Ok(Some(stream)) => {
+                                self.spawn_stream(
+                                    ctx.clone(),
+                                    &stream,
+                                    reconnecting.quality.as_str(),
+                                    reconnecting.kind,
+                                    reconnecting.reconnect,
+                                    reconnecting.auto_recorded,
+                                );
+                            }
+                            Ok(None) => {
+                                info!("{} is no longer live, not reconnecting", key);
+                                self.failed_streams.insert(
+                                    key,
+                                    FailedStream {
+                                        stream: reconnecting.stream,
+                                        quality: reconnecting.quality,
+                                        kind: reconnecting.kind,
+                                        status: reconnecting.status,
+                                        log: reconnecting.log,
+                                        ended: true,
+                                    },
+                                );
+                            }
+                            Err(e) => {
+                                error!("Unable to check if {} is live: {}", key, e);
+                                self.retry_reconnect(key, reconnecting);
+                            }
+                        }
+                    }
+                }
+                SearchCategoriesResult(query, result) => {
+                    if query == self.searched_query {
+                        match result {
+                            Ok(categories) => {
+                                self.search_categories = Some(categories);
+                            }
+                            Err(e) => {
+                                self.search_categories = Some(Vec::new());
+                                self.error_message = Some(e.to_string());
+                            }
+                        }
+                        self.searching = self.search_live_streams.is_none();
+                    }
                 }
 
                 _ => {
@@ -446,8 +2166,8 @@ impl eframe::App for App {
             });
             // auto login
             let req = TwitchMessage {
-                token: Option::from(self.token.clone()),
-                opt: TwitchOption::LoginCheck,
+                session: None,
+                opt: TwitchOption::LoginCheck(self.token.clone()),
             };
             send_req(req, self.send.clone(), Some(ctx.clone()));
             self.login_pending = false;
@@ -475,6 +2195,9 @@ impl eframe::App for App {
                 self.current_view = AppView::Streams;
                 self.request_streams(ctx.clone());
             }
+            if ui.button("Search").clicked() {
+                self.current_view = AppView::Search;
+            }
 
             ui.separator();
             ui.heading("Followed");
@@ -483,12 +2206,22 @@ impl eframe::App for App {
                 self.current_view = AppView::FollowedLive;
                 self.request_followed(Some(ctx.clone()));
             }
+            if ui.button("Channels").clicked() {
+                self.current_view = AppView::FollowedChannels;
+                self.request_followed_channels(ctx.clone());
+            }
+            if ui.button("Favorites").clicked() {
+                self.current_view = AppView::Favorites;
+                self.request_favorites(ctx.clone());
+            }
 
             ui.separator();
-            let active_count = self.active_streams.len();
-
-            if active_count > 0 {
-                if ui.button(format!("Watching {}", active_count)).clicked() {
+            if let Some(label) = watching_label(
+                self.active_streams.len(),
+                self.reconnecting_streams.len(),
+                self.failed_streams.len(),
+            ) {
+                if ui.button(label).clicked() {
                     self.current_view = AppView::Watching;
                 }
                 ui.separator();
@@ -516,6 +2249,30 @@ impl eframe::App for App {
                 });
         }
 
+        if let Some(stream) = &self.focused_stream
+            && self.qualities_login.as_ref() != Some(&stream.user_login.to_string())
+        {
+            self.request_qualities(ctx.clone(), stream.user_login.to_string());
+        }
+
+        match (&self.focused_stream, &self.session) {
+            (Some(stream), Some(session)) if self.config.show_chat => {
+                let joined = self.chat.as_ref().map(|chat| chat.channel.as_str());
+
+                if joined != Some(stream.user_login.as_str()) {
+                    self.chat_error = None;
+                    self.chat = Some(Chat::connect(
+                        TWITCH_IRC_URL,
+                        session.login().as_str(),
+                        session.access_token().as_str(),
+                        stream.user_login.as_str(),
+                        ctx.clone(),
+                    ));
+                }
+            }
+            _ => self.chat = None,
+        }
+
         if self.focused_stream.is_some() {
             egui::SidePanel::right("stream_panel").show(ctx, |ui| {
                 if ui.button("Close").clicked() {
@@ -526,19 +2283,121 @@ impl eframe::App for App {
                 let stream = self.focused_stream.clone().unwrap();
                 ui.heading(stream.user_name.as_str());
                 ui.separator();
-                ui.label(RichText::new(stream.title.as_str()).font(FontId::proportional(16.0)));
+                ui.horizontal_wrapped(|ui| {
+                    let runs = split_emote_names(&stream.title, &self.emote_names);
+                    show_runs(ui, &runs, TITLE_EMOTE_SIZE, |text| {
+                        RichText::new(text).font(FontId::proportional(16.0))
+                    });
+                });
                 ui.separator();
                 ui.label(stream.game_name.as_str());
-                ui.label(format!("{} viewers", stream.viewer_count));
+                match &self.focused_refresh {
+                    Some(refresh) if refresh.offline => {
+                        ui.colored_label(Color32::RED, "Offline");
+                    }
+                    Some(refresh) if refresh.viewers.len() > 1 => {
+                        ui.label(format!("{} viewers", stream.viewer_count));
+                        viewer_sparkline(ui, &refresh.viewers);
+                    }
+                    _ => {
+                        ui.label(format!("{} viewers", stream.viewer_count));
+                    }
+                }
+                let offline = self
+                    .focused_refresh
+                    .as_ref()
+                    .is_some_and(|refresh| refresh.offline);
+                if !offline {
+                    ui.label(format!("Live for {}", format_duration(uptime(&stream))));
+                }
+                if let Some(tag) = stream_badges(ui, &stream, self.has_list_filter()) {
+                    self.filter_by_tag(&tag);
+                }
                 ui.separator();
                 ui.horizontal(|ui| {
-                    if ui.button("Watch").clicked() {
-                        self.start_stream(&stream);
+                    let default_quality = self.config.streamlink.quality.clone();
+                    let quality = self
+                        .watch_quality
+                        .clone()
+                        .unwrap_or_else(|| default_quality.clone());
+
+                    if ui
+                        .add_enabled(!offline, egui::Button::new("Watch"))
+                        .clicked()
+                    {
+                        self.start_stream(ctx.clone(), &stream, quality.as_str());
+                        self.watch_quality = None;
+                    }
+                    if ui
+                        .add_enabled(!offline, egui::Button::new("Record"))
+                        .clicked()
+                    {
+                        self.record_stream(ctx.clone(), &stream, quality.as_str());
+                        self.watch_quality = None;
+                    }
+                    egui::ComboBox::from_id_salt("watch_quality")
+                        .selected_text(quality.as_str())
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(
+                                &mut self.watch_quality,
+                                None,
+                                format!("default ({})", default_quality),
+                            );
+                            for option in self.qualities.iter().flatten() {
+                                ui.selectable_value(
+                                    &mut self.watch_quality,
+                                    Some(option.clone()),
+                                    option.as_str(),
+                                );
+                            }
+                        });
+                    if self.qualities.is_none() {
+                        ui.spinner();
                     }
                     ui.hyperlink_to("Twitch", format!("https://twitch.tv/{}", stream.user_login));
                 });
-                let sized_thumbnail = stream.thumbnail_url.replace("{width}x{height}", "200x200");
-                ui.image(sized_thumbnail.as_str());
+
+                let login = stream.user_login.to_string();
+
+                let mut notify = !self.config.muted.contains(&login);
+                if ui.checkbox(&mut notify, "Notify when live").changed() {
+                    if notify {
+                        self.config.muted.retain(|channel| *channel != login);
+                    } else {
+                        self.config.muted.push(login.clone());
+                    }
+                    self.config.save_file();
+                }
+
+                let mut favorite = self.config.favorites.contains(&login);
+                if ui.toggle_value(&mut favorite, "★ Favorite").changed() {
+                    if favorite {
+                        self.config.favorites.push(login.clone());
+                    } else {
+                        self.config.favorites.retain(|channel| *channel != login);
+                    }
+                    self.config.save_file();
+                }
+
+                let mut auto_record = self.config.auto_record.contains(&login);
+                if ui
+                    .checkbox(&mut auto_record, "Record whenever live")
+                    .changed()
+                {
+                    if auto_record {
+                        self.config.auto_record.push(login);
+                    } else {
+                        self.config.auto_record.retain(|channel| *channel != login);
+                    }
+                    self.config.save_file();
+                }
+
+                self.stream_thumbnail(&stream, Vec2::splat(200.0)).ui(ui);
+
+                if self.chat.is_some() {
+                    ui.separator();
+                    self.show_chat(ui);
+                }
             });
         }
 
@@ -548,8 +2407,9 @@ impl eframe::App for App {
                     AppView::Login => {
                         ui.heading("Login");
                         ui.label(
-                            "Opens a browser to authorize streamgui with Twitch. Paste the \
-                        token from the page into the box and then log in.",
+                            "Opens a browser to authorize streamgui with Twitch. Login finishes \
+                        on its own once authorized, or paste the token from the page into the \
+                        box and then log in.",
                         );
                         if ui.button("Open browser").clicked() {
                             let client_id = ClientId::new(CLIENT_ID.to_owned());
@@ -559,24 +2419,52 @@ impl eframe::App for App {
                                     .expect("Invalid redirect url");
 
                             let mut builder =
-                                ImplicitUserTokenBuilder::new(client_id, redirect_url).set_scopes(
-                                    vec![
-                                        ChannelReadSubscriptions,
-                                        UserReadFollows,
-                                        UserReadSubscriptions,
-                                    ],
-                                );
+                                ImplicitUserTokenBuilder::new(client_id, redirect_url)
+                                    .set_scopes(login_scopes());
 
-                            let (url, _csrf_token) = builder.generate_url();
+                            let (url, csrf_token) = builder.generate_url();
+                            self.token_capture
+                                .expect_state(csrf_token.secret().to_owned());
 
-                            open::that(url.as_str()).expect("failed to open browser");
+                            if let Err(e) = open::that(url.as_str()) {
+                                self.error_message = Some(format!("failed to open browser: {}", e));
+                            }
                         }
                         ui.label("paste token:");
                         ui.add(TextEdit::singleline(&mut self.token).password(true));
                         if ui.button("Login").clicked() {
                             let req = TwitchMessage {
-                                token: Option::from(self.token.clone()),
-                                opt: TwitchOption::LoginCheck,
+                                session: None,
+                                opt: TwitchOption::LoginCheck(self.token.clone()),
+                            };
+                            send_req(req, self.send.clone(), Some(ctx.clone()));
+                        }
+
+                        ui.separator();
+                        ui.label(
+                            "No browser here? Authorize streamgui from any other device with a \
+                        code instead.",
+                        );
+                        if self.device_login_pending {
+                            if let Some(code) = &self.device_code {
+                                ui.horizontal(|ui| {
+                                    ui.label("Go to");
+                                    ui.hyperlink(code.verification_uri.as_str());
+                                    ui.label("and enter:");
+                                });
+                                ui.label(
+                                    RichText::new(code.user_code.as_str())
+                                        .font(FontId::monospace(24.0)),
+                                );
+                            }
+                            ui.spinner();
+                        } else if ui.button("Login with a code").clicked() {
+                            self.device_login_pending = true;
+                            self.device_code = None;
+
+                            let req = TwitchMessage {
+                                session: None,
+                                opt: TwitchOption::DeviceLogin,
                             };
                             send_req(req, self.send.clone(), Some(ctx.clone()));
                         }
@@ -588,26 +2476,31 @@ impl eframe::App for App {
                         }
 
                         let scroll_area = ScrollArea::vertical();
+                        let mut clicked_category = None;
+                        let mut retry = false;
 
-                        scroll_area.show_rows(
+                        let output = scroll_area.show_rows(
                             ui,
                             100.0,
                             self.categories.iter().len(),
                             |ui, _row_range| {
-                                for category in self.categories.iter().flatten() {
-                                    let category_button =
-                                        self.build_category_button(category.clone(), ui);
-
-                                    if category_button.response.clicked() {
-                                        self.current_view = AppView::CategoryView;
-                                        self.focused_category = Some(category.clone());
-                                        self.focused_category_streams = None;
-
-                                        self.request_category_streams(ctx.clone(), category);
-                                    }
-                                }
+                                clicked_category = self.show_categories(
+                                    self.categories.as_deref().unwrap_or_default(),
+                                    ui,
+                                );
+                                retry = self.categories_paging.footer(ui);
                             },
                         );
+
+                        if let Some(category) = clicked_category {
+                            self.current_view = AppView::CategoryView;
+                            self.request_category_streams(ctx.clone(), &category);
+                            self.focused_category = Some(category);
+                        } else if retry && self.categories.is_none() {
+                            self.request_categories(ctx.clone());
+                        } else if retry || near_bottom(&output) {
+                            self.request_more_categories(ctx.clone());
+                        }
                     }
                     AppView::Streams => {
                         ui.heading("Streams");
@@ -615,27 +2508,47 @@ impl eframe::App for App {
                             self.request_streams(ctx.clone());
                         }
 
+                        if self.config.filters.streams.show(ui, "streams") {
+                            self.config.save_file();
+                        }
+                        let streams = self
+                            .config
+                            .filters
+                            .streams
+                            .apply(self.streams.as_deref().unwrap_or_default());
+
                         let scroll_area = ScrollArea::vertical();
+                        let mut retry = false;
 
-                        scroll_area.show_rows(
+                        let output = scroll_area.show_rows(
                             ui,
                             100.0,
                             self.streams.iter().len(),
                             |ui, _row_range| {
-                                for stream in self.streams.iter().flatten() {
-                                    let stream_button =
-                                        self.build_stream_button(stream.clone(), ui);
-
-                                    if stream_button.response.clicked() {
-                                        self.focused_stream = Option::from(stream.clone());
-                                    }
-                                }
+                                let clicked = self.show_streams(&streams, ui);
+                                self.handle_stream_click(clicked);
+                                retry = self.streams_paging.footer(ui);
                             },
                         );
+
+                        if retry && self.streams.is_none() {
+                            self.request_streams(ctx.clone());
+                        } else if retry || near_bottom(&output) {
+                            self.request_more_streams(ctx.clone());
+                        }
                     }
                     AppView::FollowedLive => {
                         ui.heading("Followed Live");
 
+                        if self.config.filters.followed.show(ui, "followed") {
+                            self.config.save_file();
+                        }
+                        let streams = self
+                            .config
+                            .filters
+                            .followed
+                            .apply(self.followed_streams.as_deref().unwrap_or_default());
+
                         let scroll_area = ScrollArea::vertical();
 
                         scroll_area.show_rows(
@@ -643,18 +2556,389 @@ impl eframe::App for App {
                             100.0,
                             self.followed_streams.iter().len(),
                             |ui, _row_range| {
-                                for stream in self.followed_streams.iter().flatten() {
+                                let clicked = self.show_streams(&streams, ui);
+                                self.handle_stream_click(clicked);
+                            },
+                        );
+                    }
+                    AppView::FollowedChannels => {
+                        ui.heading("Followed Channels");
+                        if ui.button("🔄").clicked() {
+                            self.request_followed_channels(ctx.clone());
+                        }
+
+                        ScrollArea::vertical().show(ui, |ui| {
+                            let Some(channels) = &self.followed_channels else {
+                                ui.spinner();
+                                return;
+                            };
+
+                            for channel in channels {
+                                if let Some(stream) = &channel.stream {
                                     let stream_button =
                                         self.build_stream_button(stream.clone(), ui);
                                     if stream_button.response.clicked() {
                                         self.focused_stream = Option::from(stream.clone());
                                     }
+                                    continue;
                                 }
-                            },
-                        );
+
+                                let broadcaster = &channel.broadcaster;
+                                ui.horizontal(|ui| {
+                                    ui.weak(
+                                        RichText::new(broadcaster.broadcaster_name.as_str())
+                                            .size(20.0),
+                                    );
+                                    ui.weak("offline");
+                                    ui.hyperlink_to(
+                                        "Twitch",
+                                        format!(
+                                            "https://twitch.tv/{}",
+                                            broadcaster.broadcaster_login
+                                        ),
+                                    );
+                                });
+                                if let Some(information) = &channel.information
+                                    && !information.title.is_empty()
+                                {
+                                    ui.weak(format!(
+                                        "Last streamed {}: {}",
+                                        information.game_name, information.title
+                                    ));
+                                }
+                                ui.separator();
+                            }
+                        });
+                    }
+                    AppView::Favorites => {
+                        ui.heading("Favorites");
+                        if ui.button("🔄").clicked() {
+                            self.request_favorites(ctx.clone());
+                        }
+
+                        let mut removed = None;
+
+                        ScrollArea::vertical().show(ui, |ui| {
+                            let Some(live) = &self.favorite_streams else {
+                                ui.spinner();
+                                return;
+                            };
+                            if self.config.favorites.is_empty() {
+                                ui.weak("No favorites yet, add them from a stream's panel");
+                            }
+
+                            if let Some(StreamClick::Stream(stream)) = self.show_streams(live, ui) {
+                                self.focused_stream = Some(*stream);
+                            }
+
+                            for login in self.config.favorites.iter() {
+                                let is_live = live
+                                    .iter()
+                                    .any(|stream| stream.user_login.as_str() == login);
+                                if is_live {
+                                    continue;
+                                }
+
+                                ui.horizontal(|ui| {
+                                    ui.weak(RichText::new(login.as_str()).size(20.0));
+                                    ui.weak("offline");
+                                    ui.hyperlink_to(
+                                        "Twitch",
+                                        format!("https://twitch.tv/{}", login),
+                                    );
+                                    if ui.small_button("✖").clicked() {
+                                        removed = Some(login.clone());
+                                    }
+                                });
+                            }
+                        });
+
+                        if let Some(removed) = removed {
+                            self.config.favorites.retain(|channel| *channel != removed);
+                            self.config.save_file();
+                        }
+                    }
+                    AppView::Search => {
+                        ui.heading("Search");
+
+                        ui.horizontal(|ui| {
+                            let query = ui.add(
+                                TextEdit::singleline(&mut self.search_query)
+                                    .hint_text("Channel or category"),
+                            );
+                            let submitted =
+                                query.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+                            let live_only_changed = ui
+                                .checkbox(&mut self.search_live_only, "Live only")
+                                .changed();
+                            let search_clicked = ui.button("Search").clicked();
+
+                            if live_only_changed || search_clicked || submitted {
+                                self.request_search(ctx.clone());
+                            }
+                        });
+                        ui.separator();
+
+                        let mut clicked_category = None;
+
+                        ScrollArea::vertical().show(ui, |ui| {
+                            ui.heading("Channels");
+                            match &self.search_live_streams {
+                                Some(streams) => {
+                                    if let Some(StreamClick::Stream(stream)) =
+                                        self.show_streams(streams, ui)
+                                    {
+                                        self.focused_stream = Some(*stream);
+                                    }
+                                }
+                                None if self.searching => {
+                                    ui.spinner();
+                                }
+                                None => {}
+                            }
+                            for channel in self.search_offline_channels.iter().flatten() {
+                                ui.horizontal(|ui| {
+                                    ui.label(channel.display_name.as_str());
+                                    ui.weak("offline");
+                                    ui.hyperlink_to(
+                                        "Twitch",
+                                        format!("https://twitch.tv/{}", channel.broadcaster_login),
+                                    );
+                                });
+                            }
+
+                            ui.separator();
+                            ui.heading("Categories");
+                            match &self.search_categories {
+                                Some(categories) => {
+                                    clicked_category = self.show_categories(categories, ui);
+                                }
+                                None if self.searching => {
+                                    ui.spinner();
+                                }
+                                None => {}
+                            }
+                        });
+
+                        if let Some(category) = clicked_category {
+                            self.current_view = AppView::CategoryView;
+                            self.request_category_streams(ctx.clone(), &category);
+                            self.focused_category = Some(category);
+                        }
                     }
                     AppView::Settings => {
                         ui.heading("Settings");
+
+                        let mut backend = self.config.credential_backend;
+                        egui::ComboBox::from_label("Store login token in")
+                            .selected_text(backend.to_string())
+                            .show_ui(ui, |ui| {
+                                for option in
+                                    [CredentialBackend::Keyring, CredentialBackend::EncryptedFile]
+                                {
+                                    ui.selectable_value(&mut backend, option, option.to_string());
+                                }
+                            });
+                        if backend != self.config.credential_backend {
+                            let result = self.config.set_credential_backend(backend);
+                            self.show_credential_error(result);
+                        }
+
+                        ui.separator();
+                        ui.heading("Streamlink");
+
+                        let streamlink = &mut self.config.streamlink;
+                        let mut edited = false;
+
+                        egui::Grid::new("streamlink_settings")
+                            .num_columns(2)
+                            .show(ui, |ui| {
+                                ui.label("Binary");
+                                edited |=
+                                    ui.text_edit_singleline(&mut streamlink.path).lost_focus();
+                                ui.end_row();
+
+                                ui.label("Extra arguments");
+                                edited |=
+                                    ui.text_edit_singleline(&mut streamlink.args).lost_focus();
+                                ui.end_row();
+
+                                ui.label("Player");
+                                edited |= ui
+                                    .add(
+                                        TextEdit::singleline(&mut streamlink.player)
+                                            .hint_text("streamlink's default"),
+                                    )
+                                    .lost_focus();
+                                ui.end_row();
+
+                                ui.label("Player arguments");
+                                edited |= ui
+                                    .text_edit_singleline(&mut streamlink.player_args)
+                                    .lost_focus();
+                                ui.end_row();
+
+                                ui.label("Default quality");
+                                let quality = streamlink.quality.clone();
+                                egui::ComboBox::from_id_salt("default_quality")
+                                    .selected_text(quality.as_str())
+                                    .show_ui(ui, |ui| {
+                                        for option in COMMON_QUALITIES {
+                                            edited |= ui
+                                                .selectable_value(
+                                                    &mut streamlink.quality,
+                                                    option.to_owned(),
+                                                    option,
+                                                )
+                                                .clicked();
+                                        }
+                                    });
+                                ui.end_row();
+
+                                ui.label("Recording directory");
+                                let default_dir = StreamlinkConfig::default()
+                                    .recording_dir()
+                                    .display()
+                                    .to_string();
+                                edited |= ui
+                                    .add(
+                                        TextEdit::singleline(&mut streamlink.recording_dir)
+                                            .hint_text(default_dir),
+                                    )
+                                    .lost_focus();
+                                ui.end_row();
+
+                                ui.label("Recording file name");
+                                edited |= ui
+                                    .text_edit_singleline(&mut streamlink.recording_template)
+                                    .on_hover_text(
+                                        "{user_login}, {started_at} and {title} are filled in",
+                                    )
+                                    .lost_focus();
+                                ui.end_row();
+                            });
+
+                        ui.separator();
+                        ui.heading("Browse");
+
+                        ui.horizontal(|ui| {
+                            ui.label("Stream languages");
+                            let languages = ui.add(
+                                TextEdit::singleline(&mut self.stream_languages_input)
+                                    .hint_text("any")
+                                    .desired_width(120.0),
+                            );
+                            if languages.lost_focus() {
+                                self.config.stream_languages =
+                                    parse_languages(&self.stream_languages_input);
+                                self.stream_languages_input =
+                                    self.config.stream_languages.join(", ");
+                                edited = true;
+                            }
+                        })
+                        .response
+                        .on_hover_text(
+                            "Language codes such as en, de, used for Streams and categories",
+                        );
+
+                        egui::ComboBox::from_label("Show streams and categories as")
+                            .selected_text(self.config.list_layout.to_string())
+                            .show_ui(ui, |ui| {
+                                for option in [ListLayout::Grid, ListLayout::List] {
+                                    edited |= ui
+                                        .selectable_value(
+                                            &mut self.config.list_layout,
+                                            option,
+                                            option.to_string(),
+                                        )
+                                        .clicked();
+                                }
+                            });
+
+                        edited |= ui
+                            .checkbox(&mut self.config.show_chat, "Show chat in the stream panel")
+                            .changed();
+
+                        ui.horizontal(|ui| {
+                            ui.label("Refresh the stream panel every");
+                            let interval = ui.add(
+                                egui::DragValue::new(&mut self.config.focused_refresh_interval)
+                                    .range(5..=600)
+                                    .suffix(" s"),
+                            );
+                            if interval.drag_stopped() || interval.lost_focus() {
+                                edited = true;
+                            }
+                        });
+
+                        edited |= ui
+                            .checkbox(
+                                &mut self.config.blur_mature,
+                                "Blur thumbnails of mature streams",
+                            )
+                            .changed();
+
+                        ui.separator();
+                        ui.heading("Followed channels");
+
+                        ui.horizontal(|ui| {
+                            ui.label("Check for live channels every");
+                            let interval = ui.add(
+                                egui::DragValue::new(&mut self.config.poll_interval)
+                                    .range(10..=3600)
+                                    .suffix(" s"),
+                            );
+                            if interval.drag_stopped() || interval.lost_focus() {
+                                edited = true;
+                                self.start_poller(ctx.clone());
+                            }
+                        });
+
+                        edited |= ui
+                            .checkbox(
+                                &mut self.config.notifications,
+                                "Notify when followed channels go live",
+                            )
+                            .changed();
+
+                        let mut unmuted = None;
+                        for channel in self.config.muted.iter() {
+                            ui.horizontal(|ui| {
+                                ui.label(format!("{} (muted)", channel));
+                                if ui.small_button("Unmute").clicked() {
+                                    unmuted = Some(channel.clone());
+                                }
+                            });
+                        }
+                        if let Some(unmuted) = unmuted {
+                            self.config.muted.retain(|channel| *channel != unmuted);
+                            edited = true;
+                        }
+
+                        ui.label("Record whenever live:");
+                        if self.config.auto_record.is_empty() {
+                            ui.weak("No channels, add them from a stream's panel");
+                        }
+                        let mut removed = None;
+                        for channel in self.config.auto_record.iter() {
+                            ui.horizontal(|ui| {
+                                ui.label(channel.as_str());
+                                if ui.small_button("✖").clicked() {
+                                    removed = Some(channel.clone());
+                                }
+                            });
+                        }
+                        if let Some(removed) = removed {
+                            self.config
+                                .auto_record
+                                .retain(|channel| *channel != removed);
+                            edited = true;
+                        }
+
+                        if edited {
+                            self.config.save_file();
+                        }
                     }
                     AppView::CategoryView => {
                         if self.focused_category.is_none() {
@@ -675,43 +2959,203 @@ impl eframe::App for App {
                         });
 
                         ui.heading(category.name.as_str());
+                        if self.config.filters.category.show(ui, "category") {
+                            self.config.save_file();
+                        }
                         ui.separator();
 
+                        let streams =
+                            self.config.filters.category.apply(
+                                self.focused_category_streams.as_deref().unwrap_or_default(),
+                            );
+
                         let scroll_area = ScrollArea::vertical();
-                        scroll_area.show_rows(
+                        let mut retry = false;
+                        let output = scroll_area.show_rows(
                             ui,
                             100.0,
                             self.focused_category_streams.iter().len(),
                             |ui, _row_range| {
-                                for stream in self.focused_category_streams.iter().flatten() {
-                                    let stream_button =
-                                        self.build_stream_button(stream.clone(), ui);
-                                    if stream_button.response.clicked() {
-                                        self.focused_stream = Option::from(stream.clone());
-                                    }
-                                }
+                                let clicked = self.show_streams(&streams, ui);
+                                self.handle_stream_click(clicked);
+                                retry = self.focused_category_paging.footer(ui);
                             },
                         );
+
+                        if retry && self.focused_category_streams.is_none() {
+                            self.request_category_streams(ctx.clone(), &category);
+                        } else if retry || near_bottom(&output) {
+                            self.request_more_category_streams(ctx.clone(), &category);
+                        }
                     }
                     AppView::Watching => {
                         ui.heading("Watching");
 
+                        // keep the uptimes ticking
+                        ctx.request_repaint_after(Duration::from_secs(1));
+
                         let scroll_area = ScrollArea::vertical();
-                        scroll_area.show_rows(
-                            ui,
-                            100.0,
-                            self.active_streams.len(),
-                            |ui, _row_range| {
-                                for (_, stream_process) in self.active_streams.iter() {
-                                    let stream_button =
-                                        self.build_stream_button(stream_process.stream.clone(), ui);
-                                    if stream_button.response.clicked() {
-                                        self.focused_stream =
-                                            Option::from(stream_process.stream.clone());
+                        let mut dismissed = None;
+                        let mut retried = None;
+                        let mut stopped = None;
+                        let mut restarted = None;
+                        let mut toggled_reconnect = None;
+                        let mut cancelled_reconnect = None;
+
+                        scroll_area.show(ui, |ui| {
+                            for (key, failed) in self.failed_streams.iter() {
+                                ui.horizontal(|ui| {
+                                    ui.label(RichText::new(key.to_string()).size(20.0));
+                                    if failed.ended {
+                                        ui.label("Stream ended, not reconnecting");
+                                    } else {
+                                        ui.label(
+                                            RichText::new(format!("stopped: {}", failed.status))
+                                                .color(Color32::RED),
+                                        );
+                                    }
+                                    if ui.button("Retry").clicked() {
+                                        retried = Some(key.clone());
                                     }
+                                    if ui.button("Dismiss").clicked() {
+                                        dismissed = Some(key.clone());
+                                    }
+                                });
+                                ui.label(failed.stream.title.as_str());
+                                for line in failed.log.tail(FAILED_LOG_LINES) {
+                                    ui.monospace(line);
                                 }
-                            },
-                        );
+                                ui.separator();
+                            }
+
+                            for (key, reconnecting) in self.reconnecting_streams.iter() {
+                                ui.horizontal(|ui| {
+                                    ui.label(RichText::new(key.to_string()).size(20.0));
+                                    let attempt = format!(
+                                        "attempt {}/{}",
+                                        reconnecting.reconnect.attempts, MAX_RECONNECT_ATTEMPTS
+                                    );
+                                    if reconnecting.checking {
+                                        ui.label(format!("Reconnecting, {}", attempt));
+                                        ui.spinner();
+                                    } else {
+                                        let wait = reconnecting
+                                            .retry_at
+                                            .saturating_duration_since(Instant::now());
+                                        ui.label(format!(
+                                            "Reconnecting in {}s, {}",
+                                            wait.as_secs() + 1,
+                                            attempt
+                                        ));
+                                    }
+                                    if ui.button("Cancel").clicked() {
+                                        cancelled_reconnect = Some(key.clone());
+                                    }
+                                });
+                                ui.weak(format!("exited with {}", reconnecting.status));
+                                ui.separator();
+                            }
+
+                            for (key, stream_process) in self.active_streams.iter() {
+                                let stream_button =
+                                    self.build_stream_button(stream_process.stream.clone(), ui);
+                                if stream_button.response.clicked() {
+                                    self.focused_stream =
+                                        Option::from(stream_process.stream.clone());
+                                }
+                                ui.horizontal(|ui| {
+                                    ui.label(stream_process.state());
+                                    ui.label(format_duration(stream_process.started.elapsed()));
+                                    ui.weak(stream_process.quality.as_str());
+                                    if let Some(output) = &stream_process.output {
+                                        let size = fs::metadata(output).map_or(0, |m| m.len());
+                                        ui.label(format!("Recording, {}", format_size(size)));
+                                    }
+
+                                    let mut reconnect = stream_process.reconnect.enabled;
+                                    if ui.checkbox(&mut reconnect, "Reconnect").changed() {
+                                        toggled_reconnect = Some(key.clone());
+                                    }
+                                    if stream_process.reconnect.attempts > 0 {
+                                        ui.weak(format!(
+                                            "reconnected {} times",
+                                            stream_process.reconnect.attempts
+                                        ));
+                                    }
+
+                                    let running = stream_process.stopping.is_none();
+                                    if ui.add_enabled(running, egui::Button::new("Stop")).clicked()
+                                    {
+                                        stopped = Some(key.clone());
+                                    }
+                                    if ui
+                                        .add_enabled(running, egui::Button::new("Restart"))
+                                        .clicked()
+                                    {
+                                        restarted = Some(key.clone());
+                                    }
+                                    if ui.button("Open in browser").clicked()
+                                        && let Err(e) = open::that(format!(
+                                            "https://twitch.tv/{}",
+                                            stream_process.stream.user_login
+                                        ))
+                                    {
+                                        error!("failed to open browser: {}", e);
+                                    }
+                                });
+                                if let Some(output) = &stream_process.output {
+                                    ui.weak(output.display().to_string());
+                                }
+                                egui::CollapsingHeader::new("Log")
+                                    .id_salt(key)
+                                    .show(ui, |ui| {
+                                        ScrollArea::vertical()
+                                            .id_salt(key)
+                                            .max_height(200.0)
+                                            .stick_to_bottom(true)
+                                            .show(ui, |ui| {
+                                                for line in stream_process.log.lines() {
+                                                    ui.monospace(line);
+                                                }
+                                            });
+                                    });
+                            }
+                        });
+
+                        if let Some(key) = dismissed {
+                            self.failed_streams.remove(&key);
+                        }
+                        if let Some(failed) =
+                            retried.and_then(|key| self.failed_streams.remove(&key))
+                        {
+                            self.spawn_stream(
+                                ctx.clone(),
+                                &failed.stream,
+                                failed.quality.as_str(),
+                                failed.kind,
+                                Reconnect::default(),
+                                false,
+                            );
+                        }
+                        if let Some(stream_process) =
+                            stopped.and_then(|key| self.active_streams.get_mut(&key))
+                        {
+                            stream_process.stop();
+                        }
+                        if let Some(stream_process) =
+                            toggled_reconnect.and_then(|key| self.active_streams.get_mut(&key))
+                        {
+                            stream_process.reconnect.enabled = !stream_process.reconnect.enabled;
+                        }
+                        if let Some(key) = cancelled_reconnect {
+                            self.reconnecting_streams.remove(&key);
+                        }
+                        if let Some(stream_process) =
+                            restarted.and_then(|key| self.active_streams.get_mut(&key))
+                        {
+                            stream_process.restart = true;
+                            stream_process.stop();
+                        }
                     }
                 }
             })
@@ -720,88 +3164,68 @@ impl eframe::App for App {
 
     fn on_exit(&mut self, _gl: Option<&glow::Context>) {
         // try terminating the streamlink child processes and wait for them to go away
-        use nix::{
-            sys::signal::{Signal::SIGTERM, kill},
-            unistd::Pid,
-        };
-        self.active_streams
-            .iter_mut()
-            .for_each(|(_, stream_process)| {
-                if let Some(pid) = stream_process.process.id() {
-                    if let Err(e) =
-                        kill(Pid::from_raw(pid.try_into().expect("invalid pid")), SIGTERM)
-                    {
-                        eprintln!("Failed to terminate child process: {}", e);
-                    }
-                } else {
-                    stream_process
-                        .process
-                        .start_kill()
-                        .expect("Could not kill streamlink child");
-                }
-            });
+        self.active_streams.values_mut().for_each(|stream_process| {
+            stream_process.restart = false;
+            stream_process.stop();
+        });
 
         while !self.active_streams.is_empty() {
             self.monitor_children();
+            std::thread::sleep(EXIT_POLL_INTERVAL);
         }
     }
 }
 
 fn send_req(msg: TwitchMessage, tx: Sender<TwitchMessage>, ctx: Option<Context>) {
     tokio::spawn(async move {
-        if msg.token.is_none() {
-            error!("Missing token on message");
-            return;
-        }
-
-        let token = msg.token.unwrap();
-
         match msg.opt {
-            TwitchOption::LoginCheck => {
-                let result = check_login(token).await;
+            TwitchOption::LoginCheck(token) => {
+                let result = login(token).await;
                 let resp = TwitchMessage {
-                    token: None,
+                    session: None,
                     opt: LoginResult(result),
                 };
                 tx.send(resp).expect("Failed to send resp");
             }
-            GetTopCategories(pagination) => {
-                let result = get_top_categories(token, pagination).await;
-
-                let resp = TwitchMessage {
-                    token: None,
-                    opt: TopCategoriesResult(result),
-                };
-                tx.send(resp).expect("Failed to send resp");
-            }
-            GetStreams(pagination) => {
-                let result = get_streams(token, None, pagination).await;
-
-                let resp = TwitchMessage {
-                    token: None,
-                    opt: StreamsResult(result),
-                };
-                tx.send(resp).expect("Failed to send resp");
-            }
-            GetFollowedStreams => {
-                let result = get_followed_streams(token, None).await;
-
+            TwitchOption::GetQualities(streamlink, user_login) => {
+                let result = streamlink.qualities(user_login.as_str()).await;
                 let resp = TwitchMessage {
-                    token: None,
-                    opt: GetFollowedStreamsResult(result),
+                    session: None,
+                    opt: QualitiesResult(user_login, result),
                 };
                 tx.send(resp).expect("Failed to send resp");
             }
-            GetCategoryStreams(category) => {
-                let result = get_streams(token, Some(category.clone()), None).await;
+            TwitchOption::DeviceLogin => {
+                let code_tx = tx.clone();
+                let code_ctx = ctx.clone();
+
+                let urls = OAuthUrls::default();
+                let result = device_login(CLIENT_ID, login_scopes(), &urls, |code| {
+                    let resp = TwitchMessage {
+                        session: None,
+                        opt: DeviceCodeResult(code),
+                    };
+                    code_tx.send(resp).expect("Failed to send resp");
+
+                    if let Some(ctx) = code_ctx {
+                        ctx.request_repaint();
+                    }
+                })
+                .await;
 
                 let resp = TwitchMessage {
-                    token: None,
-                    opt: GetCategoryStreamsResult(result),
+                    session: None,
+                    opt: LoginResult(result),
                 };
                 tx.send(resp).expect("Failed to send resp");
             }
-            _ => {}
+            opt => match msg.session {
+                Some(session) => send_session_req(opt, &session, &tx).await,
+                None => {
+                    error!("Missing session on message");
+                    return;
+                }
+            },
         }
 
         if let Some(ctx) = ctx {
@@ -809,3 +3233,248 @@ fn send_req(msg: TwitchMessage, tx: Sender<TwitchMessage>, ctx: Option<Context>)
         }
     });
 }
+
+async fn send_session_req(opt: TwitchOption, session: &TwitchSession, tx: &Sender<TwitchMessage>) {
+    match opt {
+        GetTopCategories(generation, pagination) => {
+            let result = get_top_categories(session, pagination).await;
+
+            let resp = TwitchMessage {
+                session: None,
+                opt: TopCategoriesResult(generation, result),
+            };
+            tx.send(resp).expect("Failed to send resp");
+        }
+        GetStreams(generation, language, pagination) => {
+            let result = get_streams(session, None, language, pagination).await;
+
+            let resp = TwitchMessage {
+                session: None,
+                opt: StreamsResult(generation, result),
+            };
+            tx.send(resp).expect("Failed to send resp");
+        }
+        GetFollowedStreams => {
+            let result = get_all_followed_streams(session).await;
+
+            let resp = TwitchMessage {
+                session: None,
+                opt: GetFollowedStreamsResult(result),
+            };
+            tx.send(resp).expect("Failed to send resp");
+        }
+        GetCategoryStreams(generation, category, language, pagination) => {
+            let result = get_streams(session, Some(category.clone()), language, pagination).await;
+
+            let resp = TwitchMessage {
+                session: None,
+                opt: GetCategoryStreamsResult(generation, result),
+            };
+            tx.send(resp).expect("Failed to send resp");
+        }
+        SearchChannels(query, live_only) => {
+            let result = search_channels(session, query.clone(), live_only).await;
+
+            let resp = TwitchMessage {
+                session: None,
+                opt: SearchChannelsResult(query, live_only, result),
+            };
+            tx.send(resp).expect("Failed to send resp");
+        }
+        GetFollowedChannels => {
+            let result = get_followed_channel_status(session).await;
+
+            let resp = TwitchMessage {
+                session: None,
+                opt: FollowedChannelsResult(result),
+            };
+            tx.send(resp).expect("Failed to send resp");
+        }
+        GetFavoriteStreams(logins) => {
+            let logins = logins.into_iter().map(UserName::from).collect();
+            let result = get_streams_for_logins(session, logins).await;
+
+            let resp = TwitchMessage {
+                session: None,
+                opt: FavoriteStreamsResult(result),
+            };
+            tx.send(resp).expect("Failed to send resp");
+        }
+        GetGlobalEmotes => {
+            let result = get_global_emotes(session).await;
+
+            let resp = TwitchMessage {
+                session: None,
+                opt: GlobalEmotesResult(result),
+            };
+            tx.send(resp).expect("Failed to send resp");
+        }
+        GetFocusedStream(user_id) => {
+            let result = get_streams_for_users(session, vec![user_id.clone()])
+                .await
+                .map(|streams| streams.into_iter().next());
+
+            let resp = TwitchMessage {
+                session: None,
+                opt: FocusedStreamResult(user_id, result),
+            };
+            tx.send(resp).expect("Failed to send resp");
+        }
+        GetChannelLive(key, user_id) => {
+            let result = get_streams_for_users(session, vec![user_id])
+                .await
+                .map(|streams| streams.into_iter().next());
+
+            let resp = TwitchMessage {
+                session: None,
+                opt: ChannelLiveResult(key, result),
+            };
+            tx.send(resp).expect("Failed to send resp");
+        }
+        SearchCategories(query) => {
+            let result = search_categories(session, query.clone()).await;
+
+            let resp = TwitchMessage {
+                session: None,
+                opt: SearchCategoriesResult(query, result),
+            };
+            tx.send(resp).expect("Failed to send resp");
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Stdio;
+    use tokio::process::Command;
+
+    fn test_app() -> App {
+        let (token_send, token_recv) = std::sync::mpsc::channel();
+        let token_capture = TokenCapture::new(token_send, Context::default());
+        let config = toml::from_str("").unwrap();
+
+        App::with_config(config, token_capture, token_recv)
+    }
+
+    fn stream(login: &str) -> Stream {
+        serde_json::from_value(serde_json::json!({
+            "id": "1",
+            "user_id": "2",
+            "user_login": login,
+            "user_name": login,
+            "game_id": "509658",
+            "game_name": "Just Chatting",
+            "type": "live",
+            "title": "title",
+            "tags": [],
+            "viewer_count": 10,
+            "started_at": "2026-10-16T18:04:05Z",
+            "language": "en",
+            "thumbnail_url": "",
+            "tag_ids": [],
+            "is_mature": false
+        }))
+        .unwrap()
+    }
+
+    /// A process running `program` in place of streamlink.
+    fn stream_process(program: &str, kind: ProcessKind, reconnect: Reconnect) -> StreamProcess {
+        let mut process = Command::new(program)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+
+        StreamProcess {
+            stream: stream("alice"),
+            quality: "best".to_owned(),
+            kind,
+            output: None,
+            log: StreamLog::capture(&mut process, Context::default()),
+            process,
+            started: Instant::now(),
+            stopping: None,
+            killed: false,
+            ctx: Context::default(),
+            restart: false,
+            reconnect,
+            auto_recorded: false,
+        }
+    }
+
+    /// Run `monitor_children` until every process has been cleaned up.
+    fn wait_for_children(app: &mut App) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+
+        while !app.active_streams.is_empty() {
+            assert!(Instant::now() < deadline, "processes did not exit");
+            app.monitor_children();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    fn key(kind: ProcessKind) -> ProcessKey {
+        ProcessKey {
+            user_name: "alice".to_owned(),
+            kind,
+        }
+    }
+
+    #[test]
+    fn closed_player_is_not_reconnected() {
+        let rt = Runtime::new().unwrap();
+        let _enter = rt.enter();
+        let mut app = test_app();
+        let reconnect = Reconnect {
+            enabled: true,
+            attempts: 0,
+        };
+
+        app.active_streams.insert(
+            key(ProcessKind::Watch),
+            stream_process("true", ProcessKind::Watch, reconnect),
+        );
+        wait_for_children(&mut app);
+
+        assert!(app.reconnecting_streams.is_empty());
+        assert!(app.failed_streams.is_empty());
+    }
+
+    #[test]
+    fn failed_player_is_reconnected() {
+        let rt = Runtime::new().unwrap();
+        let _enter = rt.enter();
+        let mut app = test_app();
+        let reconnect = Reconnect {
+            enabled: true,
+            attempts: 0,
+        };
+
+        app.active_streams.insert(
+            key(ProcessKind::Watch),
+            stream_process("false", ProcessKind::Watch, reconnect),
+        );
+        wait_for_children(&mut app);
+
+        assert!(
+            app.reconnecting_streams
+                .contains_key(&key(ProcessKind::Watch))
+        );
+    }
+
+    #[test]
+    fn watching_label_counts_stopped_streams() {
+        assert_eq!(watching_label(0, 0, 0), None);
+        assert_eq!(watching_label(2, 0, 0).as_deref(), Some("Watching 2"));
+        assert_eq!(
+            watching_label(1, 0, 2).as_deref(),
+            Some("Watching 1 (2 stopped)")
+        );
+        assert_eq!(
+            watching_label(0, 1, 1).as_deref(),
+            Some("Watching 0 (1 reconnecting, 1 stopped)")
+        );
+    }
+}
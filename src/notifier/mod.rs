@@ -0,0 +1,38 @@
+use log::error;
+use std::collections::HashMap;
+use zbus::Connection;
+use zbus::zvariant::Value;
+
+const DESTINATION: &str = "org.freedesktop.Notifications";
+const PATH: &str = "/org/freedesktop/Notifications";
+
+/// Show a desktop notification through the freedesktop notification service.
+pub async fn notify(summary: &str, body: &str) -> zbus::Result<()> {
+    let connection = Connection::session().await?;
+
+    // app name, id to replace, icon, summary, body, actions, hints, timeout (-1 for the default)
+    let args = (
+        "streamgui",
+        0u32,
+        "",
+        summary,
+        body,
+        Vec::<&str>::new(),
+        HashMap::<&str, Value>::new(),
+        -1i32,
+    );
+
+    connection
+        .call_method(Some(DESTINATION), PATH, Some(DESTINATION), "Notify", &args)
+        .await?;
+    Ok(())
+}
+
+/// Show a notification in the background, logging it if that fails.
+pub fn spawn_notify(summary: String, body: String) {
+    tokio::spawn(async move {
+        if let Err(e) = notify(summary.as_str(), body.as_str()).await {
+            error!("Unable to show notification: {}", e);
+        }
+    });
+}
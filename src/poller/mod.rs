@@ -0,0 +1,265 @@
+use crate::twitch::{TwitchError, TwitchSession, get_all_followed_streams};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::{Instant, interval_at};
+use twitch_api::helix::streams::Stream;
+
+/// Where the poller gets the live followed channels from.
+pub trait FollowedSource: Send + Sync + 'static {
+    fn followed_streams(&self) -> impl Future<Output = Result<Vec<Stream>, TwitchError>> + Send;
+}
+
+impl FollowedSource for TwitchSession {
+    async fn followed_streams(&self) -> Result<Vec<Stream>, TwitchError> {
+        get_all_followed_streams(self).await
+    }
+}
+
+/// Fetch the live followed channels every `period` and hand each result to `on_poll`, until the
+/// returned task is aborted. The first poll is one period from now.
+pub fn spawn<S: FollowedSource>(
+    source: Arc<S>,
+    period: Duration,
+    on_poll: impl Fn(Result<Vec<Stream>, TwitchError>) + Send + 'static,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = interval_at(Instant::now() + period, period);
+
+        loop {
+            interval.tick().await;
+            let result = source.followed_streams().await;
+            on_poll(result);
+        }
+    })
+}
+
+/// The streams in `current` that weren't in the `previous` poll. A channel that ended its stream
+/// and started a new one between polls counts as newly live.
+pub fn newly_live(previous: &[Stream], current: &[Stream]) -> Vec<Stream> {
+    current
+        .iter()
+        .filter(|stream| !previous.iter().any(|p| p.id == stream.id))
+        .cloned()
+        .collect()
+}
+
+/// Recordings to start and stop after a poll.
+#[derive(Debug, Default, PartialEq)]
+pub struct AutoRecordChanges {
+    pub start: Vec<Stream>,
+    /// Logins of the channels to stop recording.
+    pub stop: Vec<String>,
+}
+
+/// Keeps track of the channels recorded because they are on the auto-record list.
+#[derive(Default)]
+pub struct AutoRecorder {
+    recording: HashSet<String>,
+}
+
+impl AutoRecorder {
+    /// Compare the live followed channels to what is being recorded. Channels on `auto_record`
+    /// that are live without a recording in `active` are started, which also picks up ones whose
+    /// recording failed to start or ended early. Ones that ended or were taken off the list are
+    /// stopped.
+    pub fn update(
+        &mut self,
+        auto_record: &[String],
+        live: &[Stream],
+        active: &[String],
+    ) -> AutoRecordChanges {
+        let wanted = |login: &str| {
+            auto_record
+                .iter()
+                .any(|channel| channel.eq_ignore_ascii_case(login))
+        };
+        let mut changes = AutoRecordChanges::default();
+
+        for stream in live {
+            let login = stream.user_login.to_string();
+
+            // a recording the user started themselves is theirs to stop
+            if wanted(&login) && !active.contains(&login) {
+                changes.start.push(stream.clone());
+                self.recording.insert(login);
+            }
+        }
+
+        self.recording.retain(|login| {
+            let still_live = live
+                .iter()
+                .any(|stream| stream.user_login.as_str() == login);
+
+            if still_live && wanted(login) {
+                true
+            } else {
+                changes.stop.push(login.clone());
+                false
+            }
+        });
+        changes.stop.sort();
+
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::sync::mpsc::channel;
+    use tokio::runtime::Runtime;
+
+    fn stream(login: &str) -> Stream {
+        stream_with_id(login, format!("{}-stream", login).as_str())
+    }
+
+    fn stream_with_id(login: &str, id: &str) -> Stream {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "user_id": format!("{}-id", login),
+            "user_login": login,
+            "user_name": login,
+            "game_id": "509658",
+            "game_name": "Just Chatting",
+            "type": "live",
+            "title": "title",
+            "tags": [],
+            "viewer_count": 10,
+            "started_at": "2026-10-16T18:04:05Z",
+            "language": "en",
+            "thumbnail_url": "",
+            "tag_ids": [],
+            "is_mature": false
+        }))
+        .unwrap()
+    }
+
+    fn logins(streams: &[Stream]) -> Vec<&str> {
+        streams.iter().map(|s| s.user_login.as_str()).collect()
+    }
+
+    /// Answers each poll with the next of a scripted list of live channels.
+    struct MockSource {
+        polls: Mutex<Vec<Vec<Stream>>>,
+    }
+
+    impl FollowedSource for MockSource {
+        async fn followed_streams(&self) -> Result<Vec<Stream>, TwitchError> {
+            let mut polls = self.polls.lock().unwrap();
+
+            if polls.is_empty() {
+                Ok(Vec::new())
+            } else {
+                Ok(polls.remove(0))
+            }
+        }
+    }
+
+    #[test]
+    fn newly_live_skips_streams_already_seen() {
+        let previous = [stream("alice"), stream("bob")];
+        let current = [stream("bob"), stream("carol")];
+
+        assert_eq!(logins(&newly_live(&previous, &current)), ["carol"]);
+    }
+
+    #[test]
+    fn newly_live_includes_restarted_streams() {
+        let previous = [stream_with_id("alice", "1")];
+        let current = [stream_with_id("alice", "2")];
+
+        assert_eq!(logins(&newly_live(&previous, &current)), ["alice"]);
+    }
+
+    #[test]
+    fn newly_live_is_empty_when_nothing_changed() {
+        let streams = [stream("alice")];
+
+        assert!(newly_live(&streams, &streams).is_empty());
+    }
+
+    #[test]
+    fn records_while_listed_channels_are_live() {
+        let source = MockSource {
+            polls: Mutex::new(vec![
+                vec![stream("alice"), stream("bob")],
+                vec![stream("alice"), stream("bob"), stream("carol")],
+                vec![stream("carol")],
+            ]),
+        };
+        let (tx, rx) = channel();
+
+        let rt = Runtime::new().unwrap();
+        let _enter = rt.enter();
+        let task = spawn(Arc::new(source), Duration::from_millis(5), move |result| {
+            let _ = tx.send(result);
+        });
+
+        let auto_record = vec!["Alice".to_owned(), "carol".to_owned()];
+        let mut recorder = AutoRecorder::default();
+        let mut active = Vec::new();
+        let mut poll =
+            |active: &[String]| recorder.update(&auto_record, &rx.recv().unwrap().unwrap(), active);
+
+        let changes = poll(&active);
+        assert_eq!(logins(&changes.start), ["alice"]);
+        assert!(changes.stop.is_empty());
+        active.push("alice".to_owned());
+
+        let changes = poll(&active);
+        assert_eq!(logins(&changes.start), ["carol"]);
+        assert!(changes.stop.is_empty());
+
+        active.push("carol".to_owned());
+
+        let changes = poll(&active);
+        assert!(changes.start.is_empty());
+        assert_eq!(changes.stop, ["alice"]);
+
+        task.abort();
+    }
+
+    #[test]
+    fn stops_channels_taken_off_the_list() {
+        let mut recorder = AutoRecorder::default();
+        let live = [stream("alice")];
+
+        recorder.update(&["alice".to_owned()], &live, &[]);
+        let changes = recorder.update(&[], &live, &["alice".to_owned()]);
+
+        assert!(changes.start.is_empty());
+        assert_eq!(changes.stop, ["alice"]);
+    }
+
+    #[test]
+    fn restarts_recordings_that_are_not_running() {
+        let mut recorder = AutoRecorder::default();
+        let auto_record = ["alice".to_owned()];
+        let live = [stream("alice")];
+
+        // streamlink failed to start, or the recording ended while the channel is still live
+        recorder.update(&auto_record, &live, &[]);
+        let changes = recorder.update(&auto_record, &live, &[]);
+        assert_eq!(logins(&changes.start), ["alice"]);
+
+        let changes = recorder.update(&auto_record, &live, &["alice".to_owned()]);
+        assert!(changes.start.is_empty());
+        assert!(changes.stop.is_empty());
+    }
+
+    #[test]
+    fn leaves_recordings_started_by_hand() {
+        let mut recorder = AutoRecorder::default();
+        let live = [stream("alice")];
+        let active = ["alice".to_owned()];
+
+        let changes = recorder.update(&["alice".to_owned()], &live, &active);
+        assert!(changes.start.is_empty());
+
+        let changes = recorder.update(&[], &live, &active);
+        assert!(changes.stop.is_empty());
+    }
+}
@@ -1,6 +1,8 @@
+use eframe::egui::Context;
 use http_body_util::combinators::BoxBody;
 use http_body_util::{BodyExt, Empty, Full};
 use hyper::body::Bytes;
+use hyper::header::CONTENT_TYPE;
 use hyper::server::conn::http1::Builder;
 use hyper::service::service_fn;
 use hyper::{Method, Request, Response, StatusCode};
@@ -8,11 +10,90 @@ use hyper_util::rt::{TokioIo, TokioTimer};
 use log::info;
 use std::convert::Infallible;
 use std::net::SocketAddr;
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
 use tokio::net::TcpListener;
 
 pub const PORT: u16 = 20451;
 
-pub async fn run() {
+/// Page served at the redirect url. Twitch puts the token in the url fragment, which never
+/// reaches the server, so the page posts it back to `/token` along with the returned state.
+const CAPTURE_PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<title>streamgui</title>
+<meta name="ROBOTS" content="NOFOLLOW">
+</head>
+<body>
+<p id="status">Logging in...</p>
+<script>
+  const status = document.getElementById("status");
+  const params = new URLSearchParams(window.location.hash.substring(1));
+  const query = new URLSearchParams(window.location.search);
+
+  if (params.has("access_token")) {
+    const body = new URLSearchParams({
+      access_token: params.get("access_token"),
+      state: params.get("state") || "",
+    });
+    history.replaceState(null, "", window.location.pathname);
+
+    fetch("/token", { method: "POST", body: body })
+      .then((resp) => resp.text())
+      .then((text) => { status.textContent = text; })
+      .catch(() => { status.textContent = "Unable to reach streamgui, paste the token instead."; });
+  } else if (query.has("error")) {
+    status.textContent = "Login failed: " + (query.get("error_description") || query.get("error"));
+  } else {
+    status.textContent = "No token was returned by Twitch.";
+  }
+</script>
+</body>
+</html>
+"#;
+
+/// Hands tokens posted by the browser to the app, as long as they come back with the CSRF state
+/// of the login that is in progress.
+#[derive(Clone)]
+pub struct TokenCapture {
+    pending_state: Arc<Mutex<Option<String>>>,
+    send: Sender<String>,
+    ctx: Context,
+}
+
+impl TokenCapture {
+    pub fn new(send: Sender<String>, ctx: Context) -> Self {
+        Self {
+            pending_state: Arc::new(Mutex::new(None)),
+            send,
+            ctx,
+        }
+    }
+
+    /// Remember the state from `ImplicitUserTokenBuilder::generate_url` for the next token.
+    pub fn expect_state(&self, state: String) {
+        *self.pending_state.lock().unwrap() = Some(state);
+    }
+
+    fn accept(&self, token: String, state: &str) -> bool {
+        let mut pending_state = self.pending_state.lock().unwrap();
+
+        if pending_state.as_deref() != Some(state) {
+            return false;
+        }
+
+        // each state is only good for one token
+        *pending_state = None;
+
+        if self.send.send(token).is_err() {
+            return false;
+        }
+        self.ctx.request_repaint();
+        true
+    }
+}
+
+pub async fn run(capture: TokenCapture) {
     let addr = SocketAddr::from(([127, 0, 0, 1], PORT));
 
     let listener = TcpListener::bind(addr)
@@ -27,11 +108,15 @@ pub async fn run() {
                 info!("accepted connection from {}", addr);
 
                 let io = TokioIo::new(socket);
+                let capture = capture.clone();
 
                 tokio::task::spawn(async move {
                     if let Err(err) = Builder::new()
                         .timer(TokioTimer::default())
-                        .serve_connection(io, service_fn(http_server_handler))
+                        .serve_connection(
+                            io,
+                            service_fn(|req| http_server_handler(req, capture.clone())),
+                        )
                         .await
                     {
                         info!("http error: {}", err);
@@ -47,10 +132,48 @@ pub async fn run() {
 
 async fn http_server_handler(
     req: Request<hyper::body::Incoming>,
+    capture: TokenCapture,
 ) -> Result<Response<BoxBody<Bytes, hyper::Error>>, Infallible> {
     match (req.method(), req.uri().path()) {
-        // todo have some js do fancy things
-        (&Method::GET, "/") => Ok(Response::new(full("Copy the token out of the URL above!"))),
+        (&Method::GET, "/") => {
+            let mut page = Response::new(full(CAPTURE_PAGE));
+            page.headers_mut().insert(
+                CONTENT_TYPE,
+                "text/html; charset=utf-8".parse().expect("invalid header"),
+            );
+            Ok(page)
+        }
+
+        (&Method::POST, "/token") => {
+            let body = match req.into_body().collect().await {
+                Ok(body) => body.to_bytes(),
+                Err(_) => return Ok(status_response(StatusCode::BAD_REQUEST, "Bad request")),
+            };
+
+            let mut token = None;
+            let mut state = None;
+            for (key, value) in url::form_urlencoded::parse(&body) {
+                match key.as_ref() {
+                    "access_token" => token = Some(value.into_owned()),
+                    "state" => state = Some(value.into_owned()),
+                    _ => {}
+                }
+            }
+
+            let accepted = match (token, state) {
+                (Some(token), Some(state)) => capture.accept(token, &state),
+                _ => false,
+            };
+
+            if accepted {
+                Ok(Response::new(full("Logged in, you can close this window.")))
+            } else {
+                Ok(status_response(
+                    StatusCode::FORBIDDEN,
+                    "Login state did not match, try again from streamgui.",
+                ))
+            }
+        }
 
         // Return the 404 Not Found for other routes.
         _ => {
@@ -61,6 +184,15 @@ async fn http_server_handler(
     }
 }
 
+fn status_response(
+    status: StatusCode,
+    msg: &'static str,
+) -> Response<BoxBody<Bytes, hyper::Error>> {
+    let mut resp = Response::new(full(msg));
+    *resp.status_mut() = status;
+    resp
+}
+
 fn empty() -> BoxBody<Bytes, hyper::Error> {
     Empty::<Bytes>::new()
         .map_err(|never| match never {})
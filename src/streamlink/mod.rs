@@ -0,0 +1,367 @@
+use directories_next::UserDirs;
+use eframe::egui::Context;
+use serde::de::IgnoredAny;
+use serde_derive::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+
+/// Lines of streamlink output kept for each stream.
+const LOG_LINES: usize = 500;
+
+/// Qualities twitch usually offers, for picking one without asking streamlink first.
+pub const COMMON_QUALITIES: [&str; 9] = [
+    "best",
+    "1080p60",
+    "720p60",
+    "720p",
+    "480p",
+    "360p",
+    "160p",
+    "worst",
+    "audio_only",
+];
+
+#[derive(Debug)]
+pub enum StreamlinkError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    /// streamlink ran but reported an error, such as the channel being offline.
+    Streamlink(String),
+}
+
+impl fmt::Display for StreamlinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamlinkError::Io(e) => write!(f, "failed to run streamlink: {}", e),
+            StreamlinkError::Json(e) => write!(f, "unexpected output from streamlink: {}", e),
+            StreamlinkError::Streamlink(e) => write!(f, "streamlink: {}", e),
+        }
+    }
+}
+
+impl From<std::io::Error> for StreamlinkError {
+    fn from(e: std::io::Error) -> Self {
+        StreamlinkError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for StreamlinkError {
+    fn from(e: serde_json::Error) -> Self {
+        StreamlinkError::Json(e)
+    }
+}
+
+/// The parts of `streamlink --json` output needed to list qualities.
+#[derive(Deserialize)]
+struct JsonOutput {
+    #[serde(default)]
+    streams: HashMap<String, IgnoredAny>,
+    error: Option<String>,
+}
+
+/// How streamlink is run to watch a stream.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StreamlinkConfig {
+    /// The streamlink binary, either a name looked up in `PATH` or a full path.
+    pub path: String,
+    /// Extra arguments, split on whitespace.
+    pub args: String,
+    /// Player to use instead of the one streamlink picks, passed as `--player`.
+    pub player: String,
+    /// Passed as `--player-args` when set.
+    pub player_args: String,
+    pub quality: String,
+    /// Where recordings are written, the videos directory when empty.
+    pub recording_dir: String,
+    /// File name of a recording. `{user_login}`, `{started_at}` and `{title}` are replaced with
+    /// those of the stream.
+    pub recording_template: String,
+}
+
+impl Default for StreamlinkConfig {
+    fn default() -> Self {
+        Self {
+            path: "streamlink".to_owned(),
+            args: "--twitch-low-latency".to_owned(),
+            player: String::new(),
+            player_args: String::new(),
+            quality: "best".to_owned(),
+            recording_dir: String::new(),
+            recording_template: "{user_login}_{started_at}_{title}.ts".to_owned(),
+        }
+    }
+}
+
+impl StreamlinkConfig {
+    /// Ask streamlink which qualities the channel `user_login` is currently available in.
+    pub async fn qualities(&self, user_login: &str) -> Result<Vec<String>, StreamlinkError> {
+        let output = Command::new(self.path.trim())
+            .args(self.args.split_whitespace())
+            .arg("--json")
+            .arg(format!("https://twitch.tv/{}", user_login))
+            .output()
+            .await?;
+
+        // errors are reported as json too, along with a failed exit status
+        parse_qualities(String::from_utf8_lossy(&output.stdout).as_ref())
+    }
+
+    /// The command that plays the channel `user_login` at `quality`, or writes it to `output`
+    /// instead when given.
+    pub fn command(&self, user_login: &str, quality: &str, output: Option<&Path>) -> Command {
+        let mut command = Command::new(self.path.trim());
+
+        command.args(self.args.split_whitespace());
+
+        if let Some(output) = output {
+            command.arg("--output").arg(output);
+        } else if !self.player.trim().is_empty() {
+            command.arg("--player").arg(self.player.trim());
+        }
+        if output.is_none() && !self.player_args.trim().is_empty() {
+            command.arg("--player-args").arg(self.player_args.trim());
+        }
+
+        command
+            .arg(format!("https://twitch.tv/{}", user_login))
+            .arg(quality)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        command
+    }
+
+    pub fn recording_dir(&self) -> PathBuf {
+        if !self.recording_dir.trim().is_empty() {
+            return PathBuf::from(self.recording_dir.trim());
+        }
+
+        match UserDirs::new() {
+            Some(dirs) => match dirs.video_dir() {
+                Some(dir) => dir.to_path_buf(),
+                None => dirs.home_dir().to_path_buf(),
+            },
+            None => PathBuf::from("."),
+        }
+    }
+
+    /// A path for a new recording of a stream, numbered if the name from the template is taken.
+    pub fn recording_path(&self, user_login: &str, started_at: &str, title: &str) -> PathBuf {
+        let dir = self.recording_dir();
+        let name = self
+            .recording_template
+            .replace("{user_login}", &file_name_part(user_login))
+            .replace("{started_at}", &file_name_part(started_at))
+            .replace("{title}", &file_name_part(title));
+
+        let mut path = dir.join(&name);
+        let mut n = 1;
+
+        while path.exists() {
+            let numbered = match name.rsplit_once('.') {
+                Some((stem, ext)) => format!("{}_{}.{}", stem, n, ext),
+                None => format!("{}_{}", name, n),
+            };
+            path = dir.join(numbered);
+            n += 1;
+        }
+        path
+    }
+}
+
+/// Make text safe to use in a file name on any platform, and keep it reasonably short.
+fn file_name_part(text: &str) -> String {
+    text.trim()
+        .chars()
+        .map(|c| match c {
+            c if c.is_alphanumeric() || c == '-' || c == '.' => c,
+            _ => '_',
+        })
+        .take(80)
+        .collect()
+}
+
+/// The most recent lines a streamlink process wrote to stdout and stderr.
+#[derive(Clone, Default)]
+pub struct StreamLog {
+    lines: Arc<Mutex<VecDeque<String>>>,
+}
+
+impl StreamLog {
+    /// Collect the output of `child`, which must have been spawned with piped stdout and stderr.
+    /// `ctx` is repainted as lines come in.
+    pub fn capture(child: &mut Child, ctx: Context) -> Self {
+        let log = Self::default();
+
+        if let Some(stdout) = child.stdout.take() {
+            tokio::spawn(log.clone().read(stdout, ctx.clone()));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            tokio::spawn(log.clone().read(stderr, ctx));
+        }
+        log
+    }
+
+    async fn read(self, output: impl AsyncRead + Unpin, ctx: Context) {
+        let mut lines = BufReader::new(output).lines();
+
+        while let Ok(Some(line)) = lines.next_line().await {
+            self.push(line);
+            ctx.request_repaint();
+        }
+    }
+
+    fn push(&self, line: String) {
+        let mut lines = self.lines.lock().unwrap();
+
+        if lines.len() == LOG_LINES {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        self.lines.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// The last `count` lines.
+    pub fn tail(&self, count: usize) -> Vec<String> {
+        let lines = self.lines.lock().unwrap();
+
+        lines
+            .iter()
+            .skip(lines.len().saturating_sub(count))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Read the qualities out of `streamlink --json` output, best first.
+pub fn parse_qualities(json: &str) -> Result<Vec<String>, StreamlinkError> {
+    let output: JsonOutput = serde_json::from_str(json)?;
+
+    if let Some(error) = output.error {
+        return Err(StreamlinkError::Streamlink(error));
+    }
+
+    let mut qualities: Vec<String> = output.streams.into_keys().collect();
+    qualities.sort_by_key(|quality| (quality_rank(quality), quality.clone()));
+    Ok(qualities)
+}
+
+/// Sort key putting `best` first, then resolutions from highest to lowest, then `worst` and
+/// `audio_only`, then anything else.
+fn quality_rank(quality: &str) -> (u8, Reverse<u32>, Reverse<u32>) {
+    match quality {
+        "best" => return (0, Reverse(0), Reverse(0)),
+        "worst" => return (2, Reverse(0), Reverse(0)),
+        "audio_only" => return (3, Reverse(0), Reverse(0)),
+        _ => {}
+    }
+
+    // resolutions look like 720p or 720p60
+    if let Some((height, fps)) = quality.split_once('p')
+        && let Ok(height) = height.parse()
+    {
+        if fps.is_empty() {
+            return (1, Reverse(height), Reverse(30));
+        }
+        if let Ok(fps) = fps.parse() {
+            return (1, Reverse(height), Reverse(fps));
+        }
+    }
+
+    (4, Reverse(0), Reverse(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_live_qualities_best_first() {
+        let qualities = parse_qualities(include_str!("fixtures/live.json")).unwrap();
+
+        assert_eq!(
+            qualities,
+            [
+                "best",
+                "1080p60",
+                "720p60",
+                "480p",
+                "360p",
+                "160p",
+                "worst",
+                "audio_only"
+            ]
+        );
+    }
+
+    #[test]
+    fn offline_channel_is_an_error() {
+        let result = parse_qualities(include_str!("fixtures/offline.json"));
+
+        match result {
+            Err(StreamlinkError::Streamlink(msg)) => {
+                assert!(msg.starts_with("No playable streams found"))
+            }
+            _ => panic!("expected a streamlink error"),
+        }
+    }
+
+    #[test]
+    fn same_height_sorts_by_frame_rate() {
+        let json = r#"{"streams": {"720p": {}, "720p60": {}, "1080p": {}}}"#;
+
+        assert_eq!(parse_qualities(json).unwrap(), ["1080p", "720p60", "720p"]);
+    }
+
+    #[test]
+    fn log_keeps_only_the_latest_lines() {
+        let log = StreamLog::default();
+
+        for i in 0..LOG_LINES + 2 {
+            log.push(i.to_string());
+        }
+
+        let lines = log.lines();
+        assert_eq!(lines.len(), LOG_LINES);
+        assert_eq!(lines[0], "2");
+        assert_eq!(
+            log.tail(2),
+            [(LOG_LINES).to_string(), (LOG_LINES + 1).to_string()]
+        );
+    }
+
+    #[test]
+    fn recording_path_fills_in_the_template() {
+        let config = StreamlinkConfig {
+            recording_dir: "/recordings".to_owned(),
+            ..Default::default()
+        };
+
+        let path = config.recording_path(
+            "somestreamer",
+            "2026-10-16T18:04:05Z",
+            "morning coffee / chat",
+        );
+
+        assert_eq!(
+            path,
+            Path::new("/recordings/somestreamer_2026-10-16T18_04_05Z_morning_coffee___chat.ts")
+        );
+    }
+
+    #[test]
+    fn garbage_output_is_a_json_error() {
+        let result = parse_qualities("error: unrecognized arguments: --json");
+
+        assert!(matches!(result, Err(StreamlinkError::Json(_))));
+    }
+}
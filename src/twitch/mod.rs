@@ -1,28 +1,194 @@
+use serde_derive::Serialize;
 use std::fmt;
+use std::future::Future;
 use std::option::Option;
+use std::pin::Pin;
+use std::time::Duration;
 use twitch_api::TwitchClient;
+use twitch_api::helix::channels::{
+    ChannelInformation, FollowedBroadcaster, GetChannelInformationRequest, GetFollowedChannels,
+};
+use twitch_api::helix::chat::{GetGlobalEmotesRequest, GlobalEmote};
 use twitch_api::helix::games::GetTopGamesRequest;
+use twitch_api::helix::search::{Channel, SearchCategoriesRequest, SearchChannelsRequest};
 use twitch_api::helix::streams::{GetFollowedStreamsRequest, GetStreamsRequest, Stream};
-use twitch_api::helix::{ClientRequestError, Cursor, Paginated};
-use twitch_api::twitch_oauth2::{AccessToken, TwitchToken, UserToken};
-use twitch_api::types::{CategoryId, Collection, TwitchCategory};
+use twitch_api::helix::{ClientRequestError, Cursor, Paginated, Request, RequestGet};
+use twitch_api::twitch_oauth2::client::Client;
+use twitch_api::twitch_oauth2::id::DeviceCodeResponse;
+use twitch_api::twitch_oauth2::tokens::errors::DeviceUserTokenExchangeError;
+use twitch_api::twitch_oauth2::{
+    AccessToken, DEVICE_URL, DeviceUserTokenBuilder, Scope, TOKEN_URL, TwitchToken, UserToken,
+    VALIDATE_URL,
+};
+use twitch_api::types::{CategoryId, Collection, Timestamp, TwitchCategory, UserId, UserName};
+use url::Url;
+
+/// Most ids a single helix request can filter on.
+const MAX_IDS_PER_REQUEST: usize = 100;
+/// Most languages Get Streams can filter on.
+const MAX_LANGUAGES: usize = 100;
 
 #[derive(Debug)]
 pub enum TwitchError {
     Client(ClientRequestError<reqwest::Error>),
+    DeviceCode(DeviceUserTokenExchangeError<reqwest::Error>),
     Token,
     UserId,
 }
 
 impl fmt::Display for TwitchError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "TwitchError")
+        match self {
+            TwitchError::Client(e) => write!(f, "{}", e),
+            TwitchError::DeviceCode(e) => write!(f, "{}", e),
+            TwitchError::Token => write!(f, "invalid or expired token"),
+            TwitchError::UserId => write!(f, "token is missing a user id"),
+        }
+    }
+}
+
+/// One page of results and the cursor to request the page after it, if there is one.
+pub struct Page<T> {
+    pub data: Vec<T>,
+    pub cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    fn new(data: Vec<T>, cursor: Option<Cursor>) -> Self {
+        Self {
+            data,
+            cursor: cursor.map(|c| c.to_string()),
+        }
+    }
+}
+
+/// Channels matching a search. Live channels come with their stream so they can be shown like
+/// any other stream.
+pub struct ChannelSearch {
+    pub live: Vec<Stream>,
+    pub offline: Vec<Channel>,
+}
+
+/// A followed channel, with its stream when live or what it last streamed otherwise.
+pub struct FollowedChannel {
+    pub broadcaster: FollowedBroadcaster,
+    pub stream: Option<Stream>,
+    pub information: Option<ChannelInformation>,
+}
+
+/// A client and a validated user token, created once at login and shared by every request.
+#[derive(Clone)]
+pub struct TwitchSession {
+    client: TwitchClient<'static, reqwest::Client>,
+    token: UserToken,
+}
+
+impl TwitchSession {
+    pub fn access_token(&self) -> String {
+        self.token.access_token.secret().to_owned()
+    }
+
+    /// Login of the user the token belongs to.
+    pub fn login(&self) -> String {
+        self.token.login.to_string()
+    }
+}
+
+pub async fn login(token: String) -> Result<TwitchSession, TwitchError> {
+    let client: TwitchClient<reqwest::Client> = TwitchClient::new();
+    let token = get_token(client.clone(), token).await?;
+
+    Ok(TwitchSession { client, token })
+}
+
+/// The OAuth endpoints the device code flow talks to.
+#[derive(Clone, Debug)]
+pub struct OAuthUrls {
+    pub device: Url,
+    pub token: Url,
+    pub validate: Url,
+}
+
+impl Default for OAuthUrls {
+    /// Twitch's own, or those set in the environment when built with `mock_api`.
+    fn default() -> Self {
+        Self {
+            device: DEVICE_URL.clone(),
+            token: TOKEN_URL.clone(),
+            validate: VALIDATE_URL.clone(),
+        }
     }
 }
 
-pub async fn check_login(token: String) -> bool {
+/// Sends the requests twitch_oauth2 builds for its own endpoints to `urls` instead, keeping
+/// their query.
+struct OAuthClient<'a> {
+    client: &'a reqwest::Client,
+    urls: &'a OAuthUrls,
+}
+
+impl Client for OAuthClient<'_> {
+    type Error = reqwest::Error;
+
+    fn req(
+        &self,
+        mut request: http::Request<Vec<u8>>,
+    ) -> Pin<Box<dyn Future<Output = Result<http::Response<Vec<u8>>, reqwest::Error>> + Send + '_>>
+    {
+        if let Ok(mut url) = Url::parse(&request.uri().to_string()) {
+            let query = url.query().map(str::to_owned);
+            url.set_query(None);
+
+            let redirect = [
+                (&*DEVICE_URL, &self.urls.device),
+                (&*TOKEN_URL, &self.urls.token),
+                (&*VALIDATE_URL, &self.urls.validate),
+            ]
+            .into_iter()
+            .find(|(from, _)| **from == url);
+
+            if let Some((_, to)) = redirect {
+                let mut to = to.clone();
+                to.set_query(query.as_deref());
+                if let Ok(uri) = to.as_str().parse() {
+                    *request.uri_mut() = uri;
+                }
+            }
+        }
+
+        self.client.req(request)
+    }
+}
+
+/// Log in with the OAuth device code flow against the endpoints in `urls`. `on_code` is handed
+/// the code to show the user, then this waits until the user has authorized streamgui or the
+/// code expires.
+pub async fn device_login(
+    client_id: &str,
+    scopes: Vec<Scope>,
+    urls: &OAuthUrls,
+    on_code: impl FnOnce(DeviceCodeResponse),
+) -> Result<TwitchSession, TwitchError> {
     let client: TwitchClient<reqwest::Client> = TwitchClient::new();
-    get_token(client, token).await.is_ok()
+    let oauth = OAuthClient {
+        client: client.get_client(),
+        urls,
+    };
+
+    let mut builder = DeviceUserTokenBuilder::new(client_id, scopes);
+
+    let code = builder
+        .start(&oauth)
+        .await
+        .map_err(TwitchError::DeviceCode)?;
+    on_code(code.clone());
+
+    let token = builder
+        .wait_for_code(&oauth, tokio::time::sleep)
+        .await
+        .map_err(TwitchError::DeviceCode)?;
+
+    Ok(TwitchSession { client, token })
 }
 
 pub async fn get_token(
@@ -38,74 +204,535 @@ pub async fn get_token(
 }
 
 pub async fn get_top_categories(
-    token: String,
+    session: &TwitchSession,
     pagination: Option<String>,
-) -> Result<Vec<TwitchCategory>, TwitchError> {
-    let client: TwitchClient<reqwest::Client> = TwitchClient::new();
-
-    let token = get_token(client.clone(), token).await?;
-
+) -> Result<Page<TwitchCategory>, TwitchError> {
     let mut req = GetTopGamesRequest::default().first(50);
 
     if let Some(pagination) = pagination {
         req.set_pagination(Some(Cursor::new(pagination)));
     }
 
-    let result = client.helix.req_get(req, &token).await;
+    let result = session.client.helix.req_get(req, &session.token).await;
 
     match result {
-        Ok(resp) => Ok(resp.data),
+        Ok(resp) => Ok(Page::new(resp.data, resp.pagination)),
         Err(err) => Err(TwitchError::Client(err)),
     }
 }
 
+/// Get Streams filtered on any number of languages. `GetStreamsRequest` only takes one, while
+/// helix accepts the `language` parameter repeated for each.
+#[derive(Default, Serialize)]
+struct GetLanguageStreamsRequest {
+    after: Option<Cursor>,
+    first: Option<usize>,
+    game_id: Vec<CategoryId>,
+    language: Vec<String>,
+}
+
+impl Request for GetLanguageStreamsRequest {
+    type Response = Vec<Stream>;
+
+    const PATH: &'static str = "streams";
+    const SCOPE: twitch_api::twitch_oauth2::Validator = twitch_api::twitch_oauth2::validator![];
+}
+
+impl RequestGet for GetLanguageStreamsRequest {}
+
+impl Paginated for GetLanguageStreamsRequest {
+    fn set_pagination(&mut self, cursor: Option<Cursor>) {
+        self.after = cursor;
+    }
+}
+
+/// Streams, most viewers first, in `game_id` if given and in any of `languages` unless empty.
 pub async fn get_streams(
-    token: String,
+    session: &TwitchSession,
     game_id: Option<CategoryId>,
+    languages: Vec<String>,
     pagination: Option<String>,
-) -> Result<Vec<Stream>, TwitchError> {
-    let client: TwitchClient<reqwest::Client> = TwitchClient::new();
+) -> Result<Page<Stream>, TwitchError> {
+    let mut req = GetLanguageStreamsRequest {
+        first: Some(50),
+        game_id: game_id.into_iter().collect(),
+        language: languages.into_iter().take(MAX_LANGUAGES).collect(),
+        ..Default::default()
+    };
 
-    let token = get_token(client.clone(), token).await?;
+    if let Some(pagination) = pagination {
+        req.set_pagination(Some(Cursor::new(pagination)));
+    }
 
-    let mut req = GetStreamsRequest::default().first(50);
+    let result = session.client.helix.req_get(req, &session.token).await;
 
-    if let Some(gid) = game_id {
-        req.game_id = Collection::from(vec![gid]);
+    match result {
+        Ok(resp) => Ok(Page::new(resp.data, resp.pagination)),
+        Err(err) => Err(TwitchError::Client(err)),
     }
+}
+
+pub async fn get_followed_streams(
+    session: &TwitchSession,
+    pagination: Option<String>,
+) -> Result<Page<Stream>, TwitchError> {
+    let user_id = session.token.user_id().ok_or(TwitchError::UserId)?;
+
+    let mut req = GetFollowedStreamsRequest::user_id(user_id).first(50);
 
     if let Some(pagination) = pagination {
         req.set_pagination(Some(Cursor::new(pagination)));
     }
 
-    let result = client.helix.req_get(req, &token).await;
+    let result = session.client.helix.req_get(req, &session.token).await;
 
     match result {
-        Ok(resp) => Ok(resp.data),
+        Ok(resp) => Ok(Page::new(resp.data, resp.pagination)),
         Err(err) => Err(TwitchError::Client(err)),
     }
 }
 
-pub async fn get_followed_streams(
-    token: String,
-    pagination: Option<String>,
-) -> Result<Vec<Stream>, TwitchError> {
-    let client: TwitchClient<reqwest::Client> = TwitchClient::new();
+/// Follow the cursors through every page of followed streams.
+pub async fn get_all_followed_streams(session: &TwitchSession) -> Result<Vec<Stream>, TwitchError> {
+    let mut streams = Vec::new();
+    let mut pagination = None;
 
-    let token = get_token(client.clone(), token).await?;
+    loop {
+        let page = get_followed_streams(session, pagination).await?;
+        let last_page = page.data.is_empty() || page.cursor.is_none();
 
-    let user_id = token.user_id().ok_or(TwitchError::UserId)?;
+        streams.extend(page.data);
+        pagination = page.cursor;
 
-    let mut req = GetFollowedStreamsRequest::user_id(user_id).first(50);
+        if last_page {
+            return Ok(streams);
+        }
+    }
+}
+
+pub async fn get_followed_channels(
+    session: &TwitchSession,
+    pagination: Option<String>,
+) -> Result<Page<FollowedBroadcaster>, TwitchError> {
+    let user_id = session.token.user_id().ok_or(TwitchError::UserId)?;
+
+    let mut req = GetFollowedChannels::user_id(user_id).first(100);
 
     if let Some(pagination) = pagination {
         req.set_pagination(Some(Cursor::new(pagination)));
     }
 
-    let result = client.helix.req_get(req, &token).await;
+    let result = session.client.helix.req_get(req, &session.token).await;
+
+    match result {
+        Ok(resp) => Ok(Page::new(resp.data, resp.pagination)),
+        Err(err) => Err(TwitchError::Client(err)),
+    }
+}
+
+/// Follow the cursors through every page of followed channels.
+pub async fn get_all_followed_channels(
+    session: &TwitchSession,
+) -> Result<Vec<FollowedBroadcaster>, TwitchError> {
+    let mut channels = Vec::new();
+    let mut pagination = None;
+
+    loop {
+        let page = get_followed_channels(session, pagination).await?;
+        let last_page = page.data.is_empty() || page.cursor.is_none();
+
+        channels.extend(page.data);
+        pagination = page.cursor;
+
+        if last_page {
+            return Ok(channels);
+        }
+    }
+}
+
+/// Get the channel information of the given broadcasters, batched like `get_streams_for_users`.
+pub async fn get_channel_information(
+    session: &TwitchSession,
+    broadcaster_ids: Vec<UserId>,
+) -> Result<Vec<ChannelInformation>, TwitchError> {
+    let mut information = Vec::new();
+
+    for batch in broadcaster_ids.chunks(MAX_IDS_PER_REQUEST) {
+        let req = GetChannelInformationRequest::broadcaster_ids(Collection::from(batch.to_vec()));
+
+        match session.client.helix.req_get(req, &session.token).await {
+            Ok(resp) => information.extend(resp.data),
+            Err(err) => return Err(TwitchError::Client(err)),
+        }
+    }
+
+    Ok(information)
+}
+
+/// Every followed channel, live ones first with their stream, then offline ones with their
+/// channel information.
+pub async fn get_followed_channel_status(
+    session: &TwitchSession,
+) -> Result<Vec<FollowedChannel>, TwitchError> {
+    let broadcasters = get_all_followed_channels(session).await?;
+    let mut streams = get_all_followed_streams(session).await?;
+
+    let mut live = Vec::new();
+    let mut offline = Vec::new();
+
+    for broadcaster in broadcasters {
+        match streams
+            .iter()
+            .position(|stream| stream.user_id == broadcaster.broadcaster_id)
+        {
+            Some(i) => live.push(FollowedChannel {
+                broadcaster,
+                stream: Some(streams.swap_remove(i)),
+                information: None,
+            }),
+            None => offline.push(FollowedChannel {
+                broadcaster,
+                stream: None,
+                information: None,
+            }),
+        }
+    }
+
+    let offline_ids = offline
+        .iter()
+        .map(|channel| channel.broadcaster.broadcaster_id.clone())
+        .collect();
+    let mut information = get_channel_information(session, offline_ids).await?;
+
+    for channel in offline.iter_mut() {
+        if let Some(i) = information
+            .iter()
+            .position(|info| info.broadcaster_id == channel.broadcaster.broadcaster_id)
+        {
+            channel.information = Some(information.swap_remove(i));
+        }
+    }
+
+    live.sort_by_key(|channel| {
+        std::cmp::Reverse(channel.stream.as_ref().map_or(0, |s| s.viewer_count))
+    });
+    offline.sort_by_key(|channel| channel.broadcaster.broadcaster_login.to_string());
+    live.extend(offline);
+
+    Ok(live)
+}
+
+/// Get the live streams of the given users, in batches of as many ids as one request allows.
+/// Users that aren't live are left out.
+pub async fn get_streams_for_users(
+    session: &TwitchSession,
+    user_ids: Vec<UserId>,
+) -> Result<Vec<Stream>, TwitchError> {
+    let mut streams = Vec::new();
+
+    for batch in user_ids.chunks(MAX_IDS_PER_REQUEST) {
+        let req = GetStreamsRequest::user_ids(Collection::from(batch.to_vec())).first(batch.len());
+
+        match session.client.helix.req_get(req, &session.token).await {
+            Ok(resp) => streams.extend(resp.data),
+            Err(err) => return Err(TwitchError::Client(err)),
+        }
+    }
+
+    Ok(streams)
+}
+
+/// Get the live streams of the users with the given logins, batched like
+/// `get_streams_for_users`.
+pub async fn get_streams_for_logins(
+    session: &TwitchSession,
+    user_logins: Vec<UserName>,
+) -> Result<Vec<Stream>, TwitchError> {
+    let mut streams = Vec::new();
+
+    for batch in user_logins.chunks(MAX_IDS_PER_REQUEST) {
+        let req =
+            GetStreamsRequest::user_logins(Collection::from(batch.to_vec())).first(batch.len());
+
+        match session.client.helix.req_get(req, &session.token).await {
+            Ok(resp) => streams.extend(resp.data),
+            Err(err) => return Err(TwitchError::Client(err)),
+        }
+    }
+
+    Ok(streams)
+}
+
+pub async fn search_channels(
+    session: &TwitchSession,
+    query: String,
+    live_only: bool,
+) -> Result<ChannelSearch, TwitchError> {
+    let req = SearchChannelsRequest::query(query)
+        .live_only(live_only)
+        .first(50);
+
+    let channels = match session.client.helix.req_get(req, &session.token).await {
+        Ok(resp) => resp.data,
+        Err(err) => return Err(TwitchError::Client(err)),
+    };
+
+    let (live, offline): (Vec<Channel>, Vec<Channel>) =
+        channels.into_iter().partition(|channel| channel.is_live);
+
+    let live_ids = live.iter().map(|channel| channel.id.clone()).collect();
+    let mut streams = get_streams_for_users(session, live_ids).await?;
+
+    // keep the search ranking rather than the order streams came back in
+    streams.sort_by_key(|stream| live.iter().position(|channel| channel.id == stream.user_id));
+
+    Ok(ChannelSearch {
+        live: streams,
+        offline,
+    })
+}
+
+pub async fn search_categories(
+    session: &TwitchSession,
+    query: String,
+) -> Result<Vec<TwitchCategory>, TwitchError> {
+    let req = SearchCategoriesRequest::query(query).first(50);
+
+    let result = session.client.helix.req_get(req, &session.token).await;
 
     match result {
         Ok(resp) => Ok(resp.data),
         Err(err) => Err(TwitchError::Client(err)),
     }
 }
+
+/// The emotes anyone can use in any channel.
+pub async fn get_global_emotes(session: &TwitchSession) -> Result<Vec<GlobalEmote>, TwitchError> {
+    let req = GetGlobalEmotesRequest::new();
+
+    let result = session.client.helix.req_get(req, &session.token).await;
+
+    match result {
+        Ok(resp) => Ok(resp.data),
+        Err(err) => Err(TwitchError::Client(err)),
+    }
+}
+
+/// How long a stream has been live.
+pub fn uptime(stream: &Stream) -> Duration {
+    let live_for = Timestamp::now().to_utc() - stream.started_at.to_utc();
+
+    // a start time a little ahead of the local clock is as good as just started
+    Duration::try_from(live_for).unwrap_or_default()
+}
+
+/// Language codes from a list like `en, de pt`, lowercased and without repeats.
+pub fn parse_languages(languages: &str) -> Vec<String> {
+    let mut parsed: Vec<String> = Vec::new();
+
+    for language in languages.split([',', ' ']).map(str::trim) {
+        let language = language.to_lowercase();
+        if !language.is_empty() && !parsed.contains(&language) {
+            parsed.push(language);
+        }
+    }
+    parsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_body_util::Full;
+    use hyper::body::Bytes;
+    use hyper::server::conn::http1::Builder;
+    use hyper::service::service_fn;
+    use hyper::{Response, StatusCode};
+    use hyper_util::rt::TokioIo;
+    use std::convert::Infallible;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use tokio::net::TcpListener;
+    use tokio::runtime::Runtime;
+    use twitch_api::twitch_oauth2::RequestParseError;
+
+    const PENDING: (StatusCode, &str) = (
+        StatusCode::BAD_REQUEST,
+        r#"{"status":400,"message":"authorization_pending"}"#,
+    );
+
+    /// Serve the device code flow on a local port. The token endpoint answers with `tokens` in
+    /// turn, repeating the last, and the paths that were requested are kept in `requests`.
+    async fn stand_in(
+        expires_in: u64,
+        tokens: Vec<(StatusCode, &'static str)>,
+        requests: Arc<Mutex<Vec<String>>>,
+    ) -> OAuthUrls {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let root = Url::parse(&format!(
+            "http://{}/oauth2/",
+            listener.local_addr().unwrap()
+        ))
+        .unwrap();
+        let tokens = Arc::new(tokens);
+        let token_calls = Arc::new(AtomicUsize::new(0));
+
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let tokens = tokens.clone();
+                let token_calls = token_calls.clone();
+                let requests = requests.clone();
+
+                let service = service_fn(move |req: hyper::Request<hyper::body::Incoming>| {
+                    let path = req.uri().path().to_owned();
+                    requests.lock().unwrap().push(path.clone());
+
+                    let (status, body) = match path.as_str() {
+                        "/oauth2/device" => (
+                            StatusCode::OK,
+                            format!(
+                                r#"{{"device_code":"dc","expires_in":{},"interval":0,"user_code":"ABCD","verification_uri":"https://www.twitch.tv/activate"}}"#,
+                                expires_in
+                            ),
+                        ),
+                        "/oauth2/token" => {
+                            let call = token_calls.fetch_add(1, Ordering::SeqCst);
+                            let (status, body) = tokens[call.min(tokens.len() - 1)];
+                            (status, body.to_owned())
+                        }
+                        "/oauth2/validate" => (
+                            StatusCode::OK,
+                            r#"{"client_id":"id","login":"viewer","user_id":"1","scopes":["chat:read"],"expires_in":3600}"#
+                                .to_owned(),
+                        ),
+                        _ => (StatusCode::NOT_FOUND, String::new()),
+                    };
+
+                    async move {
+                        Ok::<_, Infallible>(
+                            Response::builder()
+                                .status(status)
+                                .header("content-type", "application/json")
+                                .body(Full::new(Bytes::from(body)))
+                                .unwrap(),
+                        )
+                    }
+                });
+
+                tokio::spawn(Builder::new().serve_connection(TokioIo::new(stream), service));
+            }
+        });
+
+        OAuthUrls {
+            device: root.join("device").unwrap(),
+            token: root.join("token").unwrap(),
+            validate: root.join("validate").unwrap(),
+        }
+    }
+
+    fn run_device_login(
+        expires_in: u64,
+        tokens: Vec<(StatusCode, &'static str)>,
+    ) -> (
+        Result<TwitchSession, TwitchError>,
+        Option<String>,
+        Vec<String>,
+    ) {
+        let rt = Runtime::new().unwrap();
+        let _enter = rt.enter();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        rt.block_on(async {
+            let urls = stand_in(expires_in, tokens, requests.clone()).await;
+            let mut user_code = None;
+
+            let result = device_login("id", vec![Scope::ChatRead], &urls, |code| {
+                user_code = Some(code.user_code);
+            })
+            .await;
+
+            let requests = requests.lock().unwrap().clone();
+            (result, user_code, requests)
+        })
+    }
+
+    #[test]
+    fn device_login_waits_for_authorization() {
+        let success = (
+            StatusCode::OK,
+            r#"{"access_token":"token","refresh_token":"refresh","expires_in":3600,"scope":["chat:read"],"token_type":"bearer"}"#,
+        );
+
+        let (result, user_code, requests) = run_device_login(1800, vec![PENDING, PENDING, success]);
+        let session = result.unwrap();
+
+        assert_eq!(user_code.as_deref(), Some("ABCD"));
+        assert_eq!(session.access_token(), "token");
+        assert_eq!(session.login(), "viewer");
+        assert_eq!(
+            requests,
+            [
+                "/oauth2/device",
+                "/oauth2/token",
+                "/oauth2/token",
+                "/oauth2/token",
+                "/oauth2/validate"
+            ]
+        );
+    }
+
+    #[test]
+    fn device_login_stops_when_the_code_expires() {
+        let (result, user_code, _) = run_device_login(0, vec![PENDING]);
+
+        assert_eq!(user_code.as_deref(), Some("ABCD"));
+        assert!(matches!(
+            result,
+            Err(TwitchError::DeviceCode(
+                DeviceUserTokenExchangeError::Expired
+            ))
+        ));
+    }
+
+    #[test]
+    fn device_login_stops_when_denied() {
+        let denied = (
+            StatusCode::BAD_REQUEST,
+            r#"{"status":400,"message":"authorization_declined"}"#,
+        );
+
+        let (result, _, requests) = run_device_login(1800, vec![PENDING, denied]);
+
+        assert!(matches!(
+            result,
+            Err(TwitchError::DeviceCode(
+                DeviceUserTokenExchangeError::TokenParseError(RequestParseError::TwitchError(e))
+            )) if e.message == "authorization_declined"
+        ));
+        assert!(!requests.iter().any(|path| path == "/oauth2/validate"));
+    }
+
+    #[test]
+    fn streams_request_repeats_languages() {
+        let req = GetLanguageStreamsRequest {
+            first: Some(50),
+            game_id: vec![CategoryId::from("509658")],
+            language: vec!["en".to_owned(), "de".to_owned()],
+            ..Default::default()
+        };
+        assert_eq!(
+            req.query().unwrap(),
+            "first=50&game_id=509658&language=en&language=de"
+        );
+
+        let mut req = GetLanguageStreamsRequest::default();
+        req.set_pagination(Some(Cursor::new("abc".to_owned())));
+        assert_eq!(req.query().unwrap(), "after=abc");
+    }
+
+    #[test]
+    fn parses_language_lists() {
+        assert_eq!(parse_languages("en, DE pt,,en "), ["en", "de", "pt"]);
+        assert!(parse_languages("  ").is_empty());
+    }
+}